@@ -0,0 +1,1200 @@
+//! The BITS packet format from day 16: decoding hex transmissions into
+//! packet trees, evaluating and walking them, and encoding them back, either
+//! from a `Packet` built in code or from an expression via `Packet::assemble`.
+
+use crate::error::ParseError;
+use num_bigint::BigUint;
+use std::error::Error;
+use std::fmt;
+
+/// A transmission packed eight bits to the byte, most significant bit first.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Transmission {
+    bytes: Vec<u8>,
+    len: usize,
+}
+
+impl Transmission {
+    pub fn new() -> Transmission {
+        Transmission::default()
+    }
+
+    /// Length in bits.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Append the low `count` bits of `value`.
+    pub fn push(&mut self, value: u128, count: usize) {
+        for i in (0..count).rev() {
+            if self.len.is_multiple_of(8) {
+                self.bytes.push(0);
+            }
+            let bit = ((value >> i) & 1) as u8;
+            *self.bytes.last_mut().unwrap() |= bit << (7 - self.len % 8);
+            self.len += 1;
+        }
+    }
+
+    /// Parse hex digits, ignoring line breaks.
+    pub fn from_hex(input: &str) -> Result<Transmission, ParseError> {
+        let mut output = Transmission::new();
+        for (idx, line) in input.lines().enumerate() {
+            for (col, c) in line.chars().enumerate() {
+                let hex = c
+                    .to_digit(16)
+                    .ok_or_else(|| ParseError::invalid_char(idx, col, c, "a hex digit"))?;
+                output.push(hex as u128, 4);
+            }
+        }
+        Ok(output)
+    }
+
+    /// Read the transmission from the first bit on.
+    pub fn reader(&self) -> BitReader<'_> {
+        BitReader {
+            bytes: &self.bytes,
+            len: self.len,
+            position: 0,
+        }
+    }
+
+    /// Zero padded to whole bytes.
+    pub fn to_hex(&self) -> String {
+        self.bytes.iter().map(|b| format!("{:02X}", b)).collect()
+    }
+}
+
+/// Reads fields of a transmission in order.
+pub struct BitReader<'a> {
+    bytes: &'a [u8],
+    len: usize,
+    position: usize,
+}
+
+impl BitReader<'_> {
+    /// How many bits have been read so far.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    pub fn remaining(&self) -> usize {
+        self.len - self.position
+    }
+
+    /// Read a `count` bit field, at most 32 bits wide.
+    pub fn read(&mut self, count: usize, expected: &'static str) -> Result<u32, DecodeError> {
+        assert!(count <= 32, "can't read {} bits at once", count);
+        if count > self.remaining() {
+            return Err(DecodeError::Truncated {
+                position: self.position,
+                expected,
+            });
+        }
+        let mut value = 0;
+        for _ in 0..count {
+            let byte = self.bytes[self.position / 8];
+            value = value << 1 | ((byte >> (7 - self.position % 8)) & 1) as u32;
+            self.position += 1;
+        }
+        Ok(value)
+    }
+}
+
+/// Packets nested deeper than this are rejected rather than risking the stack.
+pub const MAX_DEPTH: usize = 256;
+
+/// Why a transmission couldn't be decoded. Positions are bit offsets from the
+/// start of the transmission.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DecodeError {
+    Hex(ParseError),
+    /// The transmission ended in the middle of a packet.
+    Truncated {
+        position: usize,
+        expected: &'static str,
+    },
+    /// The sub-packets of an operator ran past the bit length it gave.
+    Overrun {
+        position: usize,
+        end: usize,
+    },
+    /// A literal with more significant bits than fit in a u128.
+    LiteralTooLarge {
+        position: usize,
+    },
+    TooDeep {
+        position: usize,
+    },
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DecodeError::Hex(e) => write!(f, "{}", e),
+            DecodeError::Truncated { position, expected } => write!(
+                f,
+                "bit {}: expected {}, found end of transmission",
+                position, expected
+            ),
+            DecodeError::Overrun { position, end } => write!(
+                f,
+                "bit {}: sub-packets run past the end of their operator at bit {}",
+                position, end
+            ),
+            DecodeError::LiteralTooLarge { position } => {
+                write!(f, "bit {}: literal doesn't fit in 128 bits", position)
+            }
+            DecodeError::TooDeep { position } => write!(
+                f,
+                "bit {}: packets nested more than {} deep",
+                position, MAX_DEPTH
+            ),
+        }
+    }
+}
+
+impl Error for DecodeError {}
+
+impl From<ParseError> for DecodeError {
+    fn from(e: ParseError) -> DecodeError {
+        DecodeError::Hex(e)
+    }
+}
+
+/// Why a packet's value couldn't be worked out.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EvalError {
+    /// The value doesn't fit in a u128. `Packet::big_value` has no such limit.
+    Overflow(TypeID),
+    /// An operator with the wrong number of sub-packets.
+    Arity { type_id: TypeID, found: usize },
+}
+
+impl fmt::Display for EvalError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EvalError::Overflow(type_id) => {
+                write!(f, "{} overflows 128 bits", type_id.name())
+            }
+            EvalError::Arity { type_id, found } => {
+                let expected = match type_id {
+                    TypeID::Minimum | TypeID::Maximum => "at least 1 sub-packet",
+                    _ => "2 sub-packets",
+                };
+                write!(f, "{} needs {}, found {}", type_id.name(), expected, found)
+            }
+        }
+    }
+}
+
+impl Error for EvalError {}
+
+/// Why a packet couldn't be built.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EncodeError {
+    /// More than 2047 sub-packets, which also take 32768 bits or more.
+    TooLong { sub_packets: usize, bits: usize },
+}
+
+impl fmt::Display for EncodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EncodeError::TooLong { sub_packets, bits } => write!(
+                f,
+                "{} sub-packets taking {} bits can't be encoded",
+                sub_packets, bits
+            ),
+        }
+    }
+}
+
+impl Error for EncodeError {}
+
+/// Either way a transmission can fail to produce a value, for callers that
+/// decode and evaluate in one go.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PacketError {
+    Decode(DecodeError),
+    Eval(EvalError),
+}
+
+impl fmt::Display for PacketError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PacketError::Decode(e) => write!(f, "{}", e),
+            PacketError::Eval(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl Error for PacketError {}
+
+impl From<DecodeError> for PacketError {
+    fn from(e: DecodeError) -> PacketError {
+        PacketError::Decode(e)
+    }
+}
+
+impl From<EvalError> for PacketError {
+    fn from(e: EvalError) -> PacketError {
+        PacketError::Eval(e)
+    }
+}
+
+/// What a packet holds: a literal number, or an operator applied to its
+/// sub-packets. Comparisons are 1 if they hold and 0 if not.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TypeID {
+    Sum = 0,
+    Product = 1,
+    Minimum = 2,
+    Maximum = 3,
+    Literal = 4,
+    GreaterThan = 5,
+    LessThan = 6,
+    EqualTo = 7,
+}
+
+impl TypeID {
+    fn from_bits(bits: u32) -> TypeID {
+        match bits {
+            0 => TypeID::Sum,
+            1 => TypeID::Product,
+            2 => TypeID::Minimum,
+            3 => TypeID::Maximum,
+            4 => TypeID::Literal,
+            5 => TypeID::GreaterThan,
+            6 => TypeID::LessThan,
+            _ => TypeID::EqualTo,
+        }
+    }
+}
+
+/// How an operator says where its sub-packets end.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OperatorLengthTypeID {
+    /// The sub-packets take up this many bits in total.
+    BitLength(u16),
+    /// There are this many sub-packets.
+    PacketLength(u16),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum PayloadVariant {
+    Literal(u128),
+    Operator(OperatorLengthTypeID),
+}
+
+/// Callbacks for `Packet::walk`. Any `FnMut(&Packet, usize)` closure is a
+/// visitor that only looks at packets on the way in.
+pub trait Visitor {
+    /// Called with a packet and how deep it's nested, before its sub-packets.
+    fn enter(&mut self, _packet: &Packet, _depth: usize) {}
+
+    /// Called after all of a packet's sub-packets have been visited.
+    fn leave(&mut self, _packet: &Packet, _depth: usize) {}
+}
+
+impl<F: FnMut(&Packet, usize)> Visitor for F {
+    fn enter(&mut self, packet: &Packet, depth: usize) {
+        self(packet, depth)
+    }
+}
+
+/// A packet and everything nested inside it. Packets remember how they were
+/// encoded, so one that's decoded and encoded again comes out bit for bit the
+/// same.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Packet {
+    version: u8,
+    type_id: TypeID,
+    payload: PayloadVariant,
+    sub_packets: Vec<Packet>,
+    bit_length: usize,
+}
+
+impl Packet {
+    /// A literal packet using as few 4 bit groups as `value` needs.
+    pub fn literal(version: u8, value: u128) -> Packet {
+        assert!(version < 8, "version {} doesn't fit in 3 bits", version);
+        let groups = (128 - value.leading_zeros() as usize).div_ceil(4);
+        Packet {
+            version,
+            type_id: TypeID::Literal,
+            payload: PayloadVariant::Literal(value),
+            sub_packets: vec![],
+            bit_length: 6 + 5 * groups.max(1),
+        }
+    }
+
+    /// An operator packet. Uses the shorter sub-packet count encoding when
+    /// there are few enough sub-packets, otherwise their total length in bits.
+    /// Panics if neither fits.
+    pub fn operator(version: u8, type_id: TypeID, sub_packets: Vec<Packet>) -> Packet {
+        Packet::try_operator(version, type_id, sub_packets).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Like `operator`, but returns an error if the sub-packets fit neither
+    /// encoding.
+    pub fn try_operator(
+        version: u8,
+        type_id: TypeID,
+        sub_packets: Vec<Packet>,
+    ) -> Result<Packet, EncodeError> {
+        assert!(version < 8, "version {} doesn't fit in 3 bits", version);
+        assert!(type_id != TypeID::Literal, "literals have no sub-packets");
+        let sub_bits: usize = sub_packets.iter().map(|p| p.bit_length).sum();
+        let (length, header) = if sub_packets.len() < 1 << 11 {
+            (
+                OperatorLengthTypeID::PacketLength(sub_packets.len() as u16),
+                11,
+            )
+        } else if sub_bits < 1 << 15 {
+            (OperatorLengthTypeID::BitLength(sub_bits as u16), 15)
+        } else {
+            return Err(EncodeError::TooLong {
+                sub_packets: sub_packets.len(),
+                bits: sub_bits,
+            });
+        };
+        Ok(Packet {
+            version,
+            type_id,
+            payload: PayloadVariant::Operator(length),
+            sub_packets,
+            bit_length: 6 + 1 + header + sub_bits,
+        })
+    }
+
+    /// Decode a hex transmission like `"D2FE28"`. Line breaks are ignored, as
+    /// is any padding after the outermost packet.
+    pub fn parse(hex: &str) -> Result<Packet, DecodeError> {
+        Packet::decode(&Transmission::from_hex(hex)?)
+    }
+
+    /// Decode the packet at the start of `transmission`, ignoring any padding
+    /// after it.
+    pub fn decode(transmission: &Transmission) -> Result<Packet, DecodeError> {
+        Packet::read(&mut transmission.reader(), 0)
+    }
+
+    fn read(reader: &mut BitReader, depth: usize) -> Result<Packet, DecodeError> {
+        let start = reader.position();
+        if depth > MAX_DEPTH {
+            return Err(DecodeError::TooDeep { position: start });
+        }
+        let version = reader.read(3, "a packet version")? as u8;
+        let type_id = TypeID::from_bits(reader.read(3, "a packet type")?);
+
+        let mut sub_packets = vec![];
+        let payload = if type_id == TypeID::Literal {
+            let mut value: u128 = 0;
+            loop {
+                let more = reader.read(1, "a literal group")?;
+                let group = reader.read(4, "a literal group")?;
+                if value >> 124 != 0 {
+                    return Err(DecodeError::LiteralTooLarge { position: start });
+                }
+                value = value << 4 | group as u128;
+                if more == 0 {
+                    break;
+                }
+            }
+            PayloadVariant::Literal(value)
+        } else if reader.read(1, "a length type")? == 0 {
+            let length = reader.read(15, "a sub-packet bit length")?;
+            let end = reader.position() + length as usize;
+            while reader.position() < end {
+                sub_packets.push(Packet::read(reader, depth + 1)?);
+            }
+            if reader.position() > end {
+                return Err(DecodeError::Overrun {
+                    position: reader.position(),
+                    end,
+                });
+            }
+            PayloadVariant::Operator(OperatorLengthTypeID::BitLength(length as u16))
+        } else {
+            let count = reader.read(11, "a sub-packet count")?;
+            for _ in 0..count {
+                sub_packets.push(Packet::read(reader, depth + 1)?);
+            }
+            PayloadVariant::Operator(OperatorLengthTypeID::PacketLength(count as u16))
+        };
+
+        Ok(Packet {
+            version,
+            type_id,
+            payload,
+            sub_packets,
+            bit_length: reader.position() - start,
+        })
+    }
+
+    /// Encode the packet, exactly `bit_length` bits long.
+    pub fn to_bits(&self) -> Transmission {
+        let mut bits = Transmission::new();
+        self.write_bits(&mut bits);
+        bits
+    }
+
+    fn write_bits(&self, bits: &mut Transmission) {
+        bits.push(self.version as u128, 3);
+        bits.push(self.type_id as u128, 3);
+        match self.payload {
+            PayloadVariant::Literal(value) => {
+                let groups = (self.bit_length - 6) / 5;
+                for group in (0..groups).rev() {
+                    bits.push((group > 0) as u128, 1);
+                    // Decoded literals can have leading zero groups
+                    let nibble = value.checked_shr(4 * group as u32).unwrap_or(0);
+                    bits.push(nibble & 0xf, 4);
+                }
+            }
+            PayloadVariant::Operator(OperatorLengthTypeID::BitLength(length)) => {
+                bits.push(0, 1);
+                bits.push(length as u128, 15);
+            }
+            PayloadVariant::Operator(OperatorLengthTypeID::PacketLength(count)) => {
+                bits.push(1, 1);
+                bits.push(count as u128, 11);
+            }
+        }
+        for packet in &self.sub_packets {
+            packet.write_bits(bits);
+        }
+    }
+
+    /// Encode the packet as a hex transmission, zero padded to whole bytes.
+    pub fn to_hex(&self) -> String {
+        self.to_bits().to_hex()
+    }
+
+    pub fn version(&self) -> u8 {
+        self.version
+    }
+
+    pub fn type_id(&self) -> TypeID {
+        self.type_id
+    }
+
+    /// The number a literal packet holds, `None` for operators.
+    pub fn as_literal(&self) -> Option<u128> {
+        match self.payload {
+            PayloadVariant::Literal(value) => Some(value),
+            PayloadVariant::Operator(_) => None,
+        }
+    }
+
+    /// How an operator packet's length is encoded, `None` for literals.
+    pub fn length_type(&self) -> Option<OperatorLengthTypeID> {
+        match self.payload {
+            PayloadVariant::Literal(_) => None,
+            PayloadVariant::Operator(length) => Some(length),
+        }
+    }
+
+    pub fn sub_packets(&self) -> &[Packet] {
+        &self.sub_packets
+    }
+
+    /// Bits taken by this packet and all of its sub-packets, not counting
+    /// any padding.
+    pub fn bit_length(&self) -> usize {
+        self.bit_length
+    }
+
+    /// Visit this packet and everything nested in it, depth first.
+    pub fn walk(&self, visitor: &mut impl Visitor) {
+        self.walk_at(visitor, 0);
+    }
+
+    fn walk_at(&self, visitor: &mut impl Visitor, depth: usize) {
+        visitor.enter(self, depth);
+        for packet in &self.sub_packets {
+            packet.walk_at(visitor, depth + 1);
+        }
+        visitor.leave(self, depth);
+    }
+
+    /// The sum of the version numbers of every packet in the tree.
+    pub fn version_sum(&self) -> usize {
+        let mut sum = 0;
+        self.walk(&mut |packet: &Packet, _| sum += packet.version as usize);
+        sum
+    }
+
+    fn literal_value(&self) -> u128 {
+        self.as_literal()
+            .unwrap_or_else(|| panic!("{:?} isn't a literal", self.type_id))
+    }
+
+    fn check_arity(&self) -> Result<(), EvalError> {
+        let found = self.sub_packets.len();
+        let ok = match self.type_id {
+            TypeID::Minimum | TypeID::Maximum => found > 0,
+            TypeID::GreaterThan | TypeID::LessThan | TypeID::EqualTo => found == 2,
+            _ => true,
+        };
+        if ok {
+            Ok(())
+        } else {
+            Err(EvalError::Arity {
+                type_id: self.type_id,
+                found,
+            })
+        }
+    }
+
+    fn compare<T: Ord>(&self, a: &T, b: &T) -> bool {
+        match self.type_id {
+            TypeID::GreaterThan => a > b,
+            TypeID::LessThan => a < b,
+            _ => a == b,
+        }
+    }
+
+    /// Evaluate with checked arithmetic, failing rather than wrapping if
+    /// anything along the way doesn't fit in a u128.
+    pub fn value(&self) -> Result<u128, EvalError> {
+        self.check_arity()?;
+        let overflow = || EvalError::Overflow(self.type_id);
+        let mut values = self.sub_packets.iter().map(|p| p.value());
+        match self.type_id {
+            TypeID::Literal => Ok(self.literal_value()),
+            TypeID::Sum => {
+                values.try_fold(0, |acc: u128, v| acc.checked_add(v?).ok_or_else(overflow))
+            }
+            TypeID::Product => {
+                values.try_fold(1, |acc: u128, v| acc.checked_mul(v?).ok_or_else(overflow))
+            }
+            TypeID::Minimum => values.try_fold(u128::MAX, |acc, v| Ok(acc.min(v?))),
+            TypeID::Maximum => values.try_fold(0, |acc, v| Ok(acc.max(v?))),
+            _ => {
+                let a = values.next().unwrap()?;
+                let b = values.next().unwrap()?;
+                Ok(self.compare(&a, &b) as u128)
+            }
+        }
+    }
+
+    /// Evaluate exactly, however large the intermediate values get.
+    pub fn big_value(&self) -> Result<BigUint, EvalError> {
+        self.check_arity()?;
+        let values = self
+            .sub_packets
+            .iter()
+            .map(|p| p.big_value())
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(match self.type_id {
+            TypeID::Literal => BigUint::from(self.literal_value()),
+            TypeID::Sum => values.into_iter().sum(),
+            TypeID::Product => values.into_iter().product(),
+            TypeID::Minimum => values.into_iter().min().unwrap(),
+            TypeID::Maximum => values.into_iter().max().unwrap(),
+            _ => BigUint::from(self.compare(&values[0], &values[1]) as u8),
+        })
+    }
+}
+
+impl TypeID {
+    const OPERATORS: [TypeID; 7] = [
+        TypeID::Sum,
+        TypeID::Product,
+        TypeID::Minimum,
+        TypeID::Maximum,
+        TypeID::GreaterThan,
+        TypeID::LessThan,
+        TypeID::EqualTo,
+    ];
+
+    /// Lower case name, as used by the function call syntax of `Display` and
+    /// `Packet::assemble`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            TypeID::Sum => "sum",
+            TypeID::Product => "prod",
+            TypeID::Minimum => "min",
+            TypeID::Maximum => "max",
+            TypeID::Literal => "literal",
+            TypeID::GreaterThan => "gt",
+            TypeID::LessThan => "lt",
+            TypeID::EqualTo => "eq",
+        }
+    }
+
+    fn symbol(&self) -> Option<&'static str> {
+        match self {
+            TypeID::Sum => Some("+"),
+            TypeID::Product => Some("*"),
+            TypeID::GreaterThan => Some(">"),
+            TypeID::LessThan => Some("<"),
+            TypeID::EqualTo => Some("=="),
+            _ => None,
+        }
+    }
+}
+
+impl Packet {
+    // Comparisons bind loosest, then sums, then products. Literals and
+    // function calls never need parentheses.
+    fn precedence(&self) -> u8 {
+        match (self.type_id, self.sub_packets.len()) {
+            (TypeID::GreaterThan | TypeID::LessThan | TypeID::EqualTo, 2) => 1,
+            (TypeID::Sum, n) if n >= 2 => 2,
+            (TypeID::Product, n) if n >= 2 => 3,
+            _ => 4,
+        }
+    }
+
+    fn write_infix(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let PayloadVariant::Literal(value) = self.payload {
+            return write!(f, "{}", value);
+        }
+        let precedence = self.precedence();
+        for (i, packet) in self.sub_packets.iter().enumerate() {
+            match (i, precedence, self.type_id.symbol()) {
+                (0, 4, _) => write!(f, "{}(", self.type_id.name())?,
+                (_, 4, _) => write!(f, ", ")?,
+                (0, _, _) => (),
+                (_, _, symbol) => write!(f, " {} ", symbol.unwrap())?,
+            }
+            // Parenthesize anything binding as loose as us, so nested
+            // operators keep their tree shape
+            if precedence < 4 && packet.precedence() <= precedence {
+                write!(f, "(")?;
+                packet.write_infix(f)?;
+                write!(f, ")")?;
+            } else {
+                packet.write_infix(f)?;
+            }
+        }
+        if self.sub_packets.is_empty() {
+            write!(f, "{}(", self.type_id.name())?;
+        }
+        if precedence == 4 {
+            write!(f, ")")?;
+        }
+        Ok(())
+    }
+
+    /// The packet as an S-expression, e.g. `(> (max 3 (* 7 2)) 1)`.
+    pub fn to_sexpr(&self) -> String {
+        if let PayloadVariant::Literal(value) = self.payload {
+            return value.to_string();
+        }
+        let op = self.type_id.symbol().unwrap_or_else(|| self.type_id.name());
+        let mut out = format!("({}", op);
+        for packet in &self.sub_packets {
+            out.push(' ');
+            out += &packet.to_sexpr();
+        }
+        out.push(')');
+        out
+    }
+
+    /// One packet per line, indented by depth, with its version, type and
+    /// how its length was encoded.
+    pub fn to_tree(&self) -> String {
+        let mut lines = vec![];
+        self.write_tree(0, &mut lines);
+        lines.join("\n")
+    }
+
+    fn write_tree(&self, depth: usize, lines: &mut Vec<String>) {
+        let detail = match self.payload {
+            PayloadVariant::Literal(value) => format!("= {}", value),
+            PayloadVariant::Operator(OperatorLengthTypeID::BitLength(bits)) => {
+                format!("({} sub-packets in {} bits)", self.sub_packets.len(), bits)
+            }
+            PayloadVariant::Operator(OperatorLengthTypeID::PacketLength(count)) => {
+                format!("({} sub-packets by count)", count)
+            }
+        };
+        lines.push(format!(
+            "{}v{} {:?} {}",
+            "  ".repeat(depth),
+            self.version,
+            self.type_id,
+            detail
+        ));
+        for packet in &self.sub_packets {
+            packet.write_tree(depth + 1, lines);
+        }
+    }
+}
+
+/// Renders the packet as an infix expression, e.g. `max(3, 7 * 2) > 1`.
+impl fmt::Display for Packet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.write_infix(f)
+    }
+}
+
+struct Token<'a> {
+    idx: usize,
+    text: &'a str,
+}
+
+fn tokenize<'a>(lines: &[&'a str]) -> Result<Vec<Token<'a>>, ParseError> {
+    let mut tokens = vec![];
+    for (idx, line) in lines.iter().enumerate() {
+        let mut chars = line.char_indices().peekable();
+        while let Some((start, c)) = chars.next() {
+            let mut end = start + c.len_utf8();
+            if c.is_ascii_alphanumeric() {
+                while let Some(&(i, c)) = chars.peek() {
+                    if !c.is_ascii_alphanumeric() {
+                        break;
+                    }
+                    end = i + 1;
+                    chars.next();
+                }
+            } else if c == '=' {
+                if chars.next_if(|&(_, c)| c == '=').is_none() {
+                    return Err(ParseError::invalid(idx, line, &line[start..end], "=="));
+                }
+                end += 1;
+            } else if c.is_whitespace() {
+                continue;
+            } else if !"(),+*<>".contains(c) {
+                let col = line[..start].chars().count();
+                return Err(ParseError::invalid_char(idx, col, c, "an expression"));
+            }
+            tokens.push(Token {
+                idx,
+                text: &line[start..end],
+            });
+        }
+    }
+    Ok(tokens)
+}
+
+/// Recursive descent over the same grammar `Display` prints: comparisons bind
+/// loosest, then sums, then products, and every operator also has a function
+/// form named like `TypeID::name`.
+struct Assembler<'a> {
+    lines: Vec<&'a str>,
+    tokens: Vec<Token<'a>>,
+    next: usize,
+}
+
+impl Assembler<'_> {
+    fn peek(&self) -> Option<&str> {
+        self.tokens.get(self.next).map(|t| t.text)
+    }
+
+    fn error(&self, expected: &'static str) -> ParseError {
+        match self.tokens.get(self.next) {
+            Some(token) => {
+                ParseError::invalid(token.idx, self.lines[token.idx], token.text, expected)
+            }
+            None => match self.lines.last() {
+                Some(line) => ParseError::missing(self.lines.len() - 1, line, expected),
+                None => ParseError::eof(0, expected),
+            },
+        }
+    }
+
+    // `at` is the token naming the operator, which errors point at
+    fn operator(
+        &self,
+        at: usize,
+        type_id: TypeID,
+        sub_packets: Vec<Packet>,
+    ) -> Result<Packet, ParseError> {
+        Packet::try_operator(0, type_id, sub_packets).map_err(|_| {
+            let token = &self.tokens[at];
+            ParseError::invalid(
+                token.idx,
+                self.lines[token.idx],
+                token.text,
+                "an operator with few enough sub-packets to encode",
+            )
+        })
+    }
+
+    fn expect(&mut self, text: &'static str) -> Result<(), ParseError> {
+        if self.peek() != Some(text) {
+            return Err(self.error(text));
+        }
+        self.next += 1;
+        Ok(())
+    }
+
+    fn comparison(&mut self) -> Result<Packet, ParseError> {
+        let mut left = self.sum()?;
+        loop {
+            let type_id = match self.peek() {
+                Some(">") => TypeID::GreaterThan,
+                Some("<") => TypeID::LessThan,
+                Some("==") => TypeID::EqualTo,
+                _ => return Ok(left),
+            };
+            let at = self.next;
+            self.next += 1;
+            let right = self.sum()?;
+            left = self.operator(at, type_id, vec![left, right])?;
+        }
+    }
+
+    // `a + b + c` is one sum of three, `(a + b) + c` a sum inside a sum
+    fn chain(
+        &mut self,
+        type_id: TypeID,
+        mut operand: impl FnMut(&mut Self) -> Result<Packet, ParseError>,
+    ) -> Result<Packet, ParseError> {
+        let mut operands = vec![operand(self)?];
+        let at = self.next;
+        while self.peek() == type_id.symbol() {
+            self.next += 1;
+            operands.push(operand(self)?);
+        }
+        if operands.len() == 1 {
+            Ok(operands.pop().unwrap())
+        } else {
+            self.operator(at, type_id, operands)
+        }
+    }
+
+    fn sum(&mut self) -> Result<Packet, ParseError> {
+        self.chain(TypeID::Sum, Self::product)
+    }
+
+    fn product(&mut self) -> Result<Packet, ParseError> {
+        self.chain(TypeID::Product, Self::atom)
+    }
+
+    fn atom(&mut self) -> Result<Packet, ParseError> {
+        let text = self.peek().ok_or_else(|| self.error("an expression"))?;
+        if text.starts_with(|c: char| c.is_ascii_digit()) {
+            let value = text
+                .parse()
+                .map_err(|_| self.error("a number that fits in 128 bits"))?;
+            self.next += 1;
+            return Ok(Packet::literal(0, value));
+        }
+        if text == "(" {
+            self.next += 1;
+            let packet = self.comparison()?;
+            self.expect(")")?;
+            return Ok(packet);
+        }
+
+        let type_id = TypeID::OPERATORS
+            .into_iter()
+            .find(|t| t.name() == text)
+            .ok_or_else(|| self.error("an expression"))?;
+        let at = self.next;
+        self.next += 1;
+        self.expect("(")?;
+        let mut sub_packets = vec![];
+        if self.peek() != Some(")") {
+            sub_packets.push(self.comparison()?);
+            while self.peek() == Some(",") {
+                self.next += 1;
+                sub_packets.push(self.comparison()?);
+            }
+        }
+        self.expect(")")?;
+        self.operator(at, type_id, sub_packets)
+    }
+}
+
+impl Packet {
+    /// Compile an expression like `sum(1, prod(2, 3), min(4, 5)) == 11`, in
+    /// the same syntax `Display` prints, into a packet. Every packet gets
+    /// version 0.
+    pub fn assemble(source: &str) -> Result<Packet, ParseError> {
+        let lines: Vec<_> = source.lines().collect();
+        let mut assembler = Assembler {
+            tokens: tokenize(&lines)?,
+            lines,
+            next: 0,
+        };
+        let packet = assembler.comparison()?;
+        if assembler.peek().is_some() {
+            return Err(assembler.error("an operator or the end of the expression"));
+        }
+        Ok(packet)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_eval() {
+        let big = Packet::literal(0, 1 << 100);
+        let packet = Packet::operator(0, TypeID::Product, vec![big.clone(), big.clone()]);
+        assert_eq!(packet.value(), Err(EvalError::Overflow(TypeID::Product)));
+        assert_eq!(packet.big_value(), Ok(BigUint::from(1u8) << 200));
+        let packet = Packet::operator(0, TypeID::LessThan, vec![big, packet]);
+        assert_eq!(packet.value(), Err(EvalError::Overflow(TypeID::Product)));
+        assert_eq!(packet.big_value(), Ok(BigUint::from(1u8)));
+
+        // 3^500, far past 128 bits
+        let mut packet = Packet::literal(0, 1);
+        for _ in 0..500 {
+            packet = Packet::operator(0, TypeID::Product, vec![packet, Packet::literal(0, 3)]);
+        }
+        assert_eq!(packet.big_value(), Ok(BigUint::from(3u8).pow(500)));
+
+        let packet = Packet::operator(0, TypeID::Minimum, vec![]);
+        assert_eq!(
+            packet.value().unwrap_err().to_string(),
+            "min needs at least 1 sub-packet, found 0"
+        );
+        let ones = vec![Packet::literal(0, 1); 3];
+        let packet = Packet::operator(0, TypeID::EqualTo, ones);
+        assert_eq!(
+            packet.big_value(),
+            Err(EvalError::Arity {
+                type_id: TypeID::EqualTo,
+                found: 3
+            })
+        );
+    }
+
+    #[test]
+    fn test_display() {
+        let packet = Packet::parse("9C0141080250320F1802104A08").unwrap();
+        assert_eq!(packet.to_string(), "1 + 3 == 2 * 2");
+        assert_eq!(packet.to_sexpr(), "(== (+ 1 3) (* 2 2))");
+        assert_eq!(
+            packet.to_tree().lines().next(),
+            Some("v4 EqualTo (2 sub-packets in 80 bits)")
+        );
+
+        let seven_twice = Packet::operator(
+            0,
+            TypeID::Product,
+            vec![Packet::literal(0, 7), Packet::literal(0, 2)],
+        );
+        let max = Packet::operator(
+            0,
+            TypeID::Maximum,
+            vec![Packet::literal(0, 3), seven_twice.clone()],
+        );
+        let packet = Packet::operator(0, TypeID::GreaterThan, vec![max, Packet::literal(0, 1)]);
+        assert_eq!(packet.to_string(), "max(3, 7 * 2) > 1");
+        assert_eq!(packet.to_sexpr(), "(> (max 3 (* 7 2)) 1)");
+
+        let sum = Packet::operator(
+            0,
+            TypeID::Sum,
+            vec![seven_twice.clone(), Packet::literal(0, 1)],
+        );
+        let packet = Packet::operator(0, TypeID::Product, vec![sum, seven_twice]);
+        assert_eq!(packet.to_string(), "(7 * 2 + 1) * (7 * 2)");
+        let packet = Packet::operator(0, TypeID::Sum, vec![Packet::literal(0, 5)]);
+        assert_eq!(packet.to_string(), "sum(5)");
+    }
+
+    // Xorshift, so the test is repeatable without pulling in a rand crate
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        fn below(&mut self, n: u64) -> u64 {
+            self.next() % n
+        }
+    }
+
+    fn random_packet(rng: &mut Rng, depth: usize) -> Packet {
+        let version = rng.below(8) as u8;
+        if depth == 0 || rng.below(3) == 0 {
+            let value = match rng.below(3) {
+                0 => rng.below(16) as u128,
+                1 => rng.next() as u128,
+                _ => (rng.next() as u128) << 64 | rng.next() as u128,
+            };
+            return Packet::literal(version, value);
+        }
+        let type_id = TypeID::OPERATORS[rng.below(7) as usize];
+        let count = rng.below(5);
+        let sub_packets = (0..count).map(|_| random_packet(rng, depth - 1)).collect();
+        Packet::operator(version, type_id, sub_packets)
+    }
+
+    #[test]
+    fn test_round_trip() {
+        // Examples come back out exactly as they went in
+        for hex in [
+            "D2FE28",
+            "38006F45291200",
+            "EE00D40C823060",
+            "9C0141080250320F1802104A08",
+        ] {
+            let packet = Packet::parse(hex).unwrap();
+            assert_eq!(packet.to_hex(), hex);
+        }
+
+        let mut rng = Rng(0x2021_1216);
+        for _ in 0..500 {
+            let packet = random_packet(&mut rng, 4);
+            let bits = packet.to_bits();
+            assert_eq!(bits.len(), packet.bit_length);
+            assert_eq!(Packet::decode(&bits), Ok(packet.clone()));
+            assert_eq!(Packet::parse(&packet.to_hex()), Ok(packet));
+        }
+
+        // Too many sub-packets to count in 11 bits
+        let sub_packets = (0..2500).map(|n| Packet::literal(0, n % 16)).collect();
+        let packet = Packet::operator(1, TypeID::Sum, sub_packets);
+        assert!(matches!(
+            packet.payload,
+            PayloadVariant::Operator(OperatorLengthTypeID::BitLength(27500))
+        ));
+        assert_eq!(Packet::decode(&packet.to_bits()), Ok(packet));
+
+        // Too many to count, and too long to measure in 15 bits
+        let sub_packets: Vec<_> = (0..3000).map(|_| Packet::literal(0, 1)).collect();
+        assert_eq!(
+            Packet::try_operator(0, TypeID::Sum, sub_packets),
+            Err(EncodeError::TooLong {
+                sub_packets: 3000,
+                bits: 33000
+            })
+        );
+    }
+
+    #[test]
+    fn test_bad_transmissions() {
+        assert_eq!(
+            Packet::parse("D2FE").unwrap_err().to_string(),
+            "bit 16: expected a literal group, found end of transmission"
+        );
+        assert!(matches!(Packet::parse("D2XE28"), Err(DecodeError::Hex(_))));
+
+        // Says its sub-packets take 10 bits, but the one literal takes 11
+        let mut bits = Transmission::new();
+        bits.push(TypeID::Sum as u128, 6);
+        bits.push(0, 1);
+        bits.push(10, 15);
+        bits.push(TypeID::Literal as u128, 6);
+        bits.push(0b00001, 5);
+        assert_eq!(
+            Packet::decode(&bits),
+            Err(DecodeError::Overrun {
+                position: 33,
+                end: 32
+            })
+        );
+
+        let mut bits = Transmission::new();
+        for _ in 0..MAX_DEPTH + 1 {
+            bits.push(TypeID::Sum as u128, 6);
+            bits.push(1, 1);
+            bits.push(1, 11);
+        }
+        bits.push(TypeID::Literal as u128, 6);
+        bits.push(0b00001, 5);
+        assert!(matches!(
+            Packet::decode(&bits),
+            Err(DecodeError::TooDeep { .. })
+        ));
+
+        let mut bits = Transmission::new();
+        bits.push(TypeID::Literal as u128, 6);
+        for _ in 0..33 {
+            bits.push(0b11111, 5);
+        }
+        bits.push(0b01111, 5);
+        assert_eq!(
+            Packet::decode(&bits),
+            Err(DecodeError::LiteralTooLarge { position: 0 })
+        );
+    }
+
+    #[test]
+    fn test_assemble() {
+        let packet = Packet::assemble("sum(1, prod(2,3), min(4,5)) == 11").unwrap();
+        assert_eq!(packet.value(), Ok(1));
+        assert_eq!(packet.to_string(), "1 + 2 * 3 + min(4, 5) == 11");
+        assert_eq!(Packet::parse(&packet.to_hex()), Ok(packet));
+
+        let packet = Packet::assemble("(1 + 3) * 2\n  == eq(8, 8) + 7").unwrap();
+        assert_eq!(packet.to_sexpr(), "(== (* (+ 1 3) 2) (+ (== 8 8) 7))");
+
+        // Anything Display prints assembles back to the same tree
+        let mut rng = Rng(0xb175);
+        for _ in 0..500 {
+            let packet = random_packet(&mut rng, 4);
+            let assembled = Packet::assemble(&packet.to_string()).unwrap();
+            assert_eq!(assembled.to_sexpr(), packet.to_sexpr());
+        }
+
+        for (source, error) in [
+            ("", "1: expected an expression, found end of input"),
+            ("1 +", "1:4: expected an expression, found end of line"),
+            ("max(1, 2", "1:9: expected ), found end of line"),
+            (
+                "1 2",
+                "1:3: expected an operator or the end of the expression, found \"2\"",
+            ),
+            ("1 = 2", "1:3: expected ==, found \"=\""),
+            ("mean(1, 2)", "1:1: expected an expression, found \"mean\""),
+            ("sum(1,\n-2)", "2:1: expected an expression, found \"-\""),
+        ] {
+            assert_eq!(Packet::assemble(source).unwrap_err().to_string(), error);
+        }
+
+        let ones = vec!["1"; 3000];
+        let expected = "expected an operator with few enough sub-packets to encode";
+        assert_eq!(
+            Packet::assemble(&format!("0 + sum({})", ones.join(", ")))
+                .unwrap_err()
+                .to_string(),
+            format!("1:5: {}, found \"sum\"", expected)
+        );
+        assert_eq!(
+            Packet::assemble(&ones.join(" + ")).unwrap_err().to_string(),
+            format!("1:3: {}, found \"+\"", expected)
+        );
+    }
+
+    #[test]
+    fn test_walk() {
+        let packet = Packet::parse("8A004A801A8002F478").unwrap();
+        assert_eq!(packet.version_sum(), 16);
+        assert_eq!(
+            packet.length_type(),
+            Some(OperatorLengthTypeID::PacketLength(1))
+        );
+
+        // Records the versions seen on the way down and the types on the way up
+        #[derive(Default)]
+        struct Trace(Vec<(usize, u8)>, Vec<TypeID>);
+        impl Visitor for Trace {
+            fn enter(&mut self, packet: &Packet, depth: usize) {
+                self.0.push((depth, packet.version()));
+            }
+            fn leave(&mut self, packet: &Packet, _depth: usize) {
+                self.1.push(packet.type_id());
+            }
+        }
+        let mut trace = Trace::default();
+        packet.walk(&mut trace);
+        assert_eq!(trace.0, vec![(0, 4), (1, 1), (2, 5), (3, 6)]);
+        assert_eq!(trace.1.first(), Some(&TypeID::Literal));
+
+        let mut literals = vec![];
+        let packet = Packet::parse("C200B40A82").unwrap();
+        packet.walk(&mut |p: &Packet, _| literals.extend(p.as_literal()));
+        assert_eq!(literals, vec![1, 2]);
+        assert_eq!(packet.sub_packets()[0].bit_length(), 11);
+    }
+}
@@ -1,12 +1,13 @@
+use crate::error::{parse_token, ParseError};
 use std::collections::VecDeque;
 
 #[aoc_generator(day1)]
-pub fn load_input(input: &str) -> Vec<i64> {
+pub fn load_input(input: &str) -> Result<Vec<i64>, ParseError> {
     let mut output = vec![];
-    for line in input.lines() {
-        output.push(line.parse().unwrap());
+    for (idx, line) in input.lines().enumerate() {
+        output.push(parse_token(idx, line, Some(line), "a depth")?);
     }
-    output
+    Ok(output)
 }
 
 #[aoc(day1, part1)]
@@ -54,14 +55,14 @@ mod test {
     #[test]
     fn test_part1() {
         let input = read_to_string("input/2021/01.txt").unwrap();
-        let input = load_input(&input);
+        let input = load_input(&input).unwrap();
         assert_eq!(part1(&input), 7);
     }
 
     #[test]
     fn test_part2() {
         let input = read_to_string("input/2021/01.txt").unwrap();
-        let input = load_input(&input);
+        let input = load_input(&input).unwrap();
         assert_eq!(part2(&input), 5);
     }
 }
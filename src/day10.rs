@@ -1,8 +1,18 @@
+use crate::error::ParseError;
 use rayon::prelude::*;
 
 #[aoc_generator(day10)]
-pub fn load_input(input: &str) -> Vec<String> {
-    input.lines().par_bridge().map(String::from).collect()
+pub fn load_input(input: &str) -> Result<Vec<String>, ParseError> {
+    let mut output = vec![];
+    for (idx, line) in input.lines().enumerate() {
+        for (col, c) in line.chars().enumerate() {
+            if !"()[]{}<>".contains(c) {
+                return Err(ParseError::invalid_char(idx, col, c, "a bracket"));
+            }
+        }
+        output.push(String::from(line));
+    }
+    Ok(output)
 }
 
 #[aoc(day10, part1)]
@@ -92,7 +102,7 @@ pub fn part2(input: &[String]) -> i64 {
         .collect();
     scores.sort_unstable();
     let n = scores.len();
-    scores[(n / 2) as usize]
+    scores[n / 2]
 }
 
 #[cfg(test)]
@@ -103,14 +113,14 @@ mod test {
     #[test]
     fn test_part1() {
         let input = read_to_string("input/2021/10.txt").unwrap();
-        let input = load_input(&input);
+        let input = load_input(&input).unwrap();
         assert_eq!(part1(&input), 26397);
     }
 
     #[test]
     fn test_part2() {
         let input = read_to_string("input/2021/10.txt").unwrap();
-        let input = load_input(&input);
+        let input = load_input(&input).unwrap();
         assert_eq!(part2(&input), 288957);
     }
 }
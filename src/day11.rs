@@ -1,38 +1,35 @@
+use crate::error::ParseError;
+use crate::grid::Grid;
+
 struct Board {
-    counter: Vec<Vec<u32>>,
-    flash: [[bool; 10]; 10],
+    counter: Grid<u32>,
+    flash: Grid<bool>,
 }
 
 impl Board {
-    pub fn new(counter: &[Vec<u32>]) -> Board {
+    pub fn new(counter: &Grid<u32>) -> Board {
         Board {
-            counter: counter.to_vec(),
-            flash: [[false; 10]; 10],
+            counter: counter.clone(),
+            flash: counter.map(|_| false),
         }
     }
 
     pub fn increment(&mut self) {
-        for row in &mut self.counter {
-            for col in row {
-                *col += 1;
-            }
+        for value in self.counter.values_mut() {
+            *value += 1;
         }
     }
 
     pub fn flash_point(&mut self, point: (usize, usize)) -> usize {
-        let x = point.0;
-        let y = point.1;
         let mut ncnt = 0;
-        if !self.flash[x][y] && self.counter[x][y] > 9 {
-            self.flash[x][y] = true;
+        if !self.flash[point] && self.counter[point] > 9 {
+            self.flash[point] = true;
             ncnt += 1;
             // Increment neighbors
-            let neighbors = get_neighbors((x, y));
+            let neighbors: Vec<_> = self.counter.neighbors8(point).collect();
             for neighbor in neighbors {
-                let nx = neighbor.0;
-                let ny = neighbor.1;
-                if !self.flash[nx][ny] {
-                    self.counter[nx][ny] += 1;
+                if !self.flash[neighbor] {
+                    self.counter[neighbor] += 1;
                 }
                 ncnt += self.flash_point(neighbor);
             }
@@ -43,12 +40,11 @@ impl Board {
     pub fn flash(&mut self) -> usize {
         let mut ncnt = 0;
         let mut last_ncnt = ncnt;
+        let points: Vec<_> = self.counter.positions().collect();
         loop {
             // Check board
-            for x in 0..10 {
-                for y in 0..10 {
-                    ncnt += self.flash_point((x, y));
-                }
+            for point in &points {
+                ncnt += self.flash_point(*point);
             }
 
             if ncnt == last_ncnt {
@@ -58,74 +54,29 @@ impl Board {
         }
 
         // Reset state
-        self.flash = [[false; 10]; 10];
-        for x in 0..10 {
-            for y in 0..10 {
-                if self.counter[x][y] >= 10 {
-                    self.counter[x][y] = 0;
-                }
+        for flashed in self.flash.values_mut() {
+            *flashed = false;
+        }
+        for value in self.counter.values_mut() {
+            if *value >= 10 {
+                *value = 0;
             }
         }
         ncnt
     }
 
     pub fn check_sync(&self) -> bool {
-        for x in 0..10 {
-            for y in 0..10 {
-                if self.counter[x][y] != 0 {
-                    return false;
-                }
-            }
-        }
-        true
+        self.counter.values().all(|&v| v == 0)
     }
 }
 
 #[aoc_generator(day11)]
-pub fn load_input(input: &str) -> Vec<Vec<u32>> {
-    let mut output = vec![];
-    for line in input.lines() {
-        let mut out_line = vec![];
-        for c in line.chars() {
-            out_line.push(c.to_digit(10).unwrap());
-        }
-        output.push(out_line);
-    }
-    output
-}
-
-fn get_neighbors(point: (usize, usize)) -> Vec<(usize, usize)> {
-    let mut points = vec![];
-    if point.0 > 0 {
-        points.push((point.0 - 1, point.1));
-        if point.1 > 0 {
-            points.push((point.0 - 1, point.1 - 1));
-        }
-        if point.1 < 9 {
-            points.push((point.0 - 1, point.1 + 1));
-        }
-    }
-    if point.0 < 9 {
-        points.push((point.0 + 1, point.1));
-        if point.1 > 0 {
-            points.push((point.0 + 1, point.1 - 1));
-        }
-        if point.1 < 9 {
-            points.push((point.0 + 1, point.1 + 1));
-        }
-    }
-    if point.1 > 0 {
-        points.push((point.0, point.1 - 1));
-    }
-    if point.1 < 9 {
-        points.push((point.0, point.1 + 1));
-    }
-
-    points
+pub fn load_input(input: &str) -> Result<Grid<u32>, ParseError> {
+    Grid::parse_digits(input)
 }
 
 #[aoc(day11, part1)]
-pub fn part1(input: &[Vec<u32>]) -> usize {
+pub fn part1(input: &Grid<u32>) -> usize {
     let mut score = 0;
     let mut board = Board::new(input);
 
@@ -140,7 +91,7 @@ pub fn part1(input: &[Vec<u32>]) -> usize {
 }
 
 #[aoc(day11, part2)]
-pub fn part2(input: &[Vec<u32>]) -> usize {
+pub fn part2(input: &Grid<u32>) -> usize {
     let mut board = Board::new(input);
     let mut idx = 0;
     loop {
@@ -162,14 +113,14 @@ mod test {
     #[test]
     fn test_part1() {
         let input = read_to_string("input/2021/11.txt").unwrap();
-        let input = load_input(&input);
+        let input = load_input(&input).unwrap();
         assert_eq!(part1(&input), 1656);
     }
 
     #[test]
     fn test_part2() {
         let input = read_to_string("input/2021/11.txt").unwrap();
-        let input = load_input(&input);
+        let input = load_input(&input).unwrap();
         assert_eq!(part2(&input), 195);
     }
 }
@@ -1,3 +1,4 @@
+use crate::error::{next_token, ParseError};
 use std::collections::{HashMap, HashSet};
 
 #[derive(Clone, Debug, Eq, Hash, PartialEq)]
@@ -147,12 +148,17 @@ pub fn can_visit2(path: &[String], cave: &str) -> bool {
 }
 
 #[aoc_generator(day12)]
-pub fn load_input(input: &str) -> CaveSystem {
+pub fn load_input(input: &str) -> Result<CaveSystem, ParseError> {
     let mut cave_system = CaveSystem::new();
-    for line in input.lines() {
+    for (idx, line) in input.lines().enumerate() {
         let mut linegen = line.split('-');
-        let left = linegen.next().unwrap();
-        let right = linegen.next().unwrap();
+        let left = next_token(idx, line, &mut linegen, "a cave name")?;
+        let right = next_token(idx, line, &mut linegen, "'-'")?;
+        for name in [left, right] {
+            if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphabetic()) {
+                return Err(ParseError::invalid(idx, line, name, "a cave name"));
+            }
+        }
         cave_system.caves.insert(Cave::new(left));
         cave_system.caves.insert(Cave::new(right));
         cave_system.edges.insert(Edge {
@@ -160,7 +166,7 @@ pub fn load_input(input: &str) -> CaveSystem {
             node1: String::from(right),
         });
     }
-    cave_system
+    Ok(cave_system)
 }
 
 #[aoc(day12, part1)]
@@ -194,30 +200,30 @@ mod test {
     #[test]
     fn test_part1() {
         let input = read_to_string("input/2021/12a.txt").unwrap();
-        let input = load_input(&input);
+        let input = load_input(&input).unwrap();
         assert_eq!(part1(&input), 10);
 
         let input = read_to_string("input/2021/12b.txt").unwrap();
-        let input = load_input(&input);
+        let input = load_input(&input).unwrap();
         assert_eq!(part1(&input), 19);
 
         let input = read_to_string("input/2021/12c.txt").unwrap();
-        let input = load_input(&input);
+        let input = load_input(&input).unwrap();
         assert_eq!(part1(&input), 226);
     }
 
     #[test]
     fn test_part2() {
         let input = read_to_string("input/2021/12a.txt").unwrap();
-        let input = load_input(&input);
+        let input = load_input(&input).unwrap();
         assert_eq!(part2(&input), 36);
 
         let input = read_to_string("input/2021/12b.txt").unwrap();
-        let input = load_input(&input);
+        let input = load_input(&input).unwrap();
         assert_eq!(part2(&input), 103);
 
         let input = read_to_string("input/2021/12c.txt").unwrap();
-        let input = load_input(&input);
+        let input = load_input(&input).unwrap();
         assert_eq!(part2(&input), 3509);
     }
 }
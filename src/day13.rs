@@ -1,3 +1,4 @@
+use crate::error::{next_token, parse_token, ParseError};
 use std::collections::HashMap;
 
 #[derive(Clone, Debug)]
@@ -6,33 +7,35 @@ pub struct Fold {
     pub number: usize,
 }
 
+#[allow(clippy::type_complexity)]
 #[aoc_generator(day13)]
-pub fn load_input(input: &str) -> (HashMap<(usize, usize), u8>, Vec<Fold>) {
+pub fn load_input(input: &str) -> Result<(HashMap<(usize, usize), u8>, Vec<Fold>), ParseError> {
     let mut folds = vec![];
     let mut output = HashMap::<(usize, usize), u8>::new();
-    for line in input.lines() {
+    for (idx, line) in input.lines().enumerate() {
         if line.is_empty() {
-        } else if line.contains("fold along") {
-            let mut ll = line.split(' ');
-            ll.next();
-            ll.next();
-            let eq = ll.next().unwrap();
+        } else if let Some(eq) = line.strip_prefix("fold along ") {
             let mut ee = eq.split('=');
-            let orient = ee.next().unwrap().chars().next().unwrap();
-            let number = ee.next().unwrap().parse::<usize>().unwrap();
+            let axis = next_token(idx, line, &mut ee, "an axis")?;
+            let orientation = match axis {
+                "x" => 'x',
+                "y" => 'y',
+                _ => return Err(ParseError::invalid(idx, line, axis, "x or y")),
+            };
+            let number = parse_token(idx, line, ee.next(), "a fold line")?;
             folds.push(Fold {
-                orientation: orient,
+                orientation,
                 number,
             });
         } else {
             let mut ll = line.split(',');
-            let x = ll.next().unwrap().parse::<usize>().unwrap();
-            let y = ll.next().unwrap().parse::<usize>().unwrap();
+            let x = parse_token(idx, line, ll.next(), "an x coordinate")?;
+            let y = parse_token(idx, line, ll.next(), "a y coordinate")?;
             output.insert((x, y), 1);
         }
     }
 
-    (output, folds)
+    Ok((output, folds))
 }
 
 pub fn get_max(board: &HashMap<(usize, usize), u8>) -> usize {
@@ -89,17 +92,27 @@ pub fn count_dots(board: &HashMap<(usize, usize), u8>) -> usize {
     output
 }
 
-pub fn print_paper(board: &HashMap<(usize, usize), u8>) {
-    for y in 0..10 {
-        for x in 0..64 {
-            if board.get(&(x, y)).is_some() {
-                print!("#");
-            } else {
-                print!(" ");
-            }
-        }
-        println!();
+pub fn render_paper(board: &HashMap<(usize, usize), u8>) -> String {
+    let width = board.keys().map(|p| p.0 + 1).max().unwrap_or(0);
+    let height = board.keys().map(|p| p.1 + 1).max().unwrap_or(0);
+    let mut rows = vec![];
+    for y in 0..height {
+        let row: String = (0..width)
+            .map(|x| {
+                if board.contains_key(&(x, y)) {
+                    '#'
+                } else {
+                    ' '
+                }
+            })
+            .collect();
+        rows.push(row);
     }
+    rows.join("\n")
+}
+
+pub fn print_paper(board: &HashMap<(usize, usize), u8>) {
+    println!("{}", render_paper(board));
 }
 
 #[aoc(day13, part1)]
@@ -111,15 +124,14 @@ pub fn part1(input: &(HashMap<(usize, usize), u8>, Vec<Fold>)) -> usize {
 }
 
 #[aoc(day13, part2)]
-pub fn part2(input: &(HashMap<(usize, usize), u8>, Vec<Fold>)) -> usize {
+pub fn part2(input: &(HashMap<(usize, usize), u8>, Vec<Fold>)) -> String {
     let mut board = input.0.clone();
     let folds = input.1.clone();
     for fold in &folds {
         do_fold(&mut board, fold);
     }
 
-    print_paper(&board);
-    0
+    render_paper(&board)
 }
 
 #[cfg(test)]
@@ -130,14 +142,14 @@ mod test {
     #[test]
     fn test_part1() {
         let input = read_to_string("input/2021/13.txt").unwrap();
-        let input = load_input(&input);
+        let input = load_input(&input).unwrap();
         assert_eq!(part1(&input), 17);
     }
 
     #[test]
     fn test_part2() {
         let input = read_to_string("input/2021/13.txt").unwrap();
-        let input = load_input(&input);
-        assert_eq!(part2(&input), 0);
+        let input = load_input(&input).unwrap();
+        assert_eq!(part2(&input), "#####\n#   #\n#   #\n#   #\n#####");
     }
 }
@@ -1,3 +1,4 @@
+use crate::error::{next_token, ParseError};
 use nalgebra::*;
 use std::collections::{HashMap, HashSet};
 
@@ -7,21 +8,49 @@ pub struct InputData(
     HashMap<(char, char), ((char, char), (char, char))>,
 );
 
+fn parse_pair(idx: usize, line: &str, pair: &str) -> Result<(char, char), ParseError> {
+    let mut keyiter = pair.chars();
+    match (keyiter.next(), keyiter.next(), keyiter.next()) {
+        (Some(first), Some(second), None)
+            if first.is_ascii_uppercase() && second.is_ascii_uppercase() =>
+        {
+            Ok((first, second))
+        }
+        _ => Err(ParseError::invalid(idx, line, pair, "a pair of elements")),
+    }
+}
+
 #[aoc_generator(day14)]
-pub fn load_input(input: &str) -> InputData {
-    let seed = input.lines().next().unwrap().chars().collect();
+pub fn load_input(input: &str) -> Result<InputData, ParseError> {
+    let first = input
+        .lines()
+        .next()
+        .ok_or_else(|| ParseError::eof(0, "a polymer template"))?;
+    for (col, c) in first.chars().enumerate() {
+        if !c.is_ascii_uppercase() {
+            return Err(ParseError::invalid_char(0, col, c, "an element"));
+        }
+    }
+    if first.len() < 2 {
+        return Err(ParseError::missing(0, first, "at least 2 elements"));
+    }
+    let seed = first.chars().collect();
+
     let mut mapping = HashMap::<(char, char), ((char, char), (char, char))>::new();
-    for line in input.lines().skip(2) {
+    for (idx, line) in input.lines().enumerate().skip(2) {
         let mut iter = line.split(" -> ");
-        let mut keyiter = iter.next().unwrap().chars();
-        let first = keyiter.next().unwrap();
-        let second = keyiter.next().unwrap();
-        let right = iter.next().unwrap().chars().next().unwrap();
+        let (first, second) = parse_pair(idx, line, next_token(idx, line, &mut iter, "a pair")?)?;
+        let rightstr = next_token(idx, line, &mut iter, "' -> '")?;
+        let mut rightiter = rightstr.chars();
+        let right = match (rightiter.next(), rightiter.next()) {
+            (Some(c), None) if c.is_ascii_uppercase() => c,
+            _ => return Err(ParseError::invalid(idx, line, rightstr, "an element")),
+        };
         let lvalue = (first, right);
         let rvalue = (right, second);
         mapping.insert((first, second), (lvalue, rvalue));
     }
-    InputData(seed, mapping)
+    Ok(InputData(seed, mapping))
 }
 
 pub fn create_input_vector(input: &[char], pairvec: &[(char, char)]) -> Vec<usize> {
@@ -140,14 +169,14 @@ mod test {
     #[test]
     fn test_part1() {
         let input = read_to_string("input/2021/14.txt").unwrap();
-        let input = load_input(&input);
+        let input = load_input(&input).unwrap();
         assert_eq!(part1(&input), 1588);
     }
 
     #[test]
     fn test_part2() {
         let input = read_to_string("input/2021/14.txt").unwrap();
-        let input = load_input(&input);
+        let input = load_input(&input).unwrap();
         assert_eq!(part2(&input), 2188189693529);
     }
 }
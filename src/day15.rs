@@ -1,131 +1,208 @@
-use std::collections::HashMap;
+use crate::error::ParseError;
+use crate::grid::{Grid, NEIGHBORS4};
+use crate::search::{astar, Route};
+use std::collections::HashSet;
+use termion::{color, style};
 
 #[aoc_generator(day15)]
-pub fn load_input(input: &str) -> Vec<Vec<u32>> {
-    let mut output = vec![];
-    for line in input.lines() {
-        let mut newline = vec![];
-        for c in line.chars() {
-            newline.push(c.to_digit(10).unwrap());
-        }
-        output.push(newline);
-    }
-    output
+pub fn load_input(input: &str) -> Result<Grid<u32>, ParseError> {
+    Grid::parse(input, "a risk from 1 to 9", |c| {
+        c.to_digit(10).filter(|&risk| risk > 0)
+    })
 }
 
-struct Explorer {
-    map: Vec<Vec<u32>>,
-    costmap: HashMap<(u32, u32), u32>,
+/// The map repeated `factor` times in both directions, the risk going up by
+/// one per tile away from the top left and wrapping from 9 back to 1. Risks
+/// are worked out on the fly, so big factors don't cost any memory.
+#[derive(Clone, Copy, Debug)]
+pub struct TiledMap<'a> {
+    map: &'a Grid<u32>,
+    factor: usize,
 }
 
-impl Explorer {
-    pub fn new(map: &[Vec<u32>]) -> Explorer {
-        Explorer {
-            map: map.to_vec(),
-            costmap: HashMap::new(),
-        }
+impl<'a> TiledMap<'a> {
+    pub fn new(map: &'a Grid<u32>, factor: usize) -> TiledMap<'a> {
+        TiledMap { map, factor }
     }
 
-    pub fn explore2(&mut self, goal: (u32, u32)) -> u32 {
-        // First we slap the start into the frontier set
-        let mut frontier = HashMap::new();
-        let start_point = (0_u32, 0_u32);
-        frontier.insert(start_point, 0_u32);
-        self.costmap.insert(start_point, 0);
-
-        // Now we consider each point in frontier, start
-        while !frontier.is_empty() {
-            // Grab the lowest cost frontier points first
-            let mut cost_arr: Vec<_> = frontier.values().copied().collect();
-            cost_arr.sort_unstable();
-            let mut point = *frontier.keys().next().unwrap();
-            for (k, v) in frontier.iter() {
-                if *v == cost_arr[0] {
-                    point = *k;
-                }
-            }
-
-            frontier.remove(&point);
-            let current_cost = *self.costmap.get(&point).unwrap();
-
-            let neighbors = get_neighbors(point, &self.map);
-            for next in neighbors {
-                let next_cost = self.map[next.0 as usize][next.1 as usize];
-                let cost = current_cost + next_cost;
-                if let Some(old_cost) = self.costmap.get_mut(&next) {
-                    if *old_cost > cost {
-                        *old_cost = cost;
-                        frontier.insert(next, cost);
-                    }
-                } else {
-                    self.costmap.insert(next, cost);
-                    frontier.insert(next, cost);
-                }
-            }
-        }
+    pub fn width(&self) -> usize {
+        self.map.width() * self.factor
+    }
+
+    pub fn height(&self) -> usize {
+        self.map.height() * self.factor
+    }
+
+    pub fn contains(&self, point: (usize, usize)) -> bool {
+        point.0 < self.width() && point.1 < self.height()
+    }
+
+    /// A 0, which `load_input` never lets through, counts as 9 in the first
+    /// tile and goes up from there like any other risk, so every step still
+    /// costs at least 1.
+    pub fn risk(&self, (x, y): (usize, usize)) -> u32 {
+        let (w, h) = (self.map.width(), self.map.height());
+        let base = self.map[(x % w, y % h)];
+        (base + 8 + (x / w + y / h) as u32) % 9 + 1
+    }
 
-        return *self.costmap.get(&goal).unwrap();
+    pub fn neighbors4(&self, point: (usize, usize)) -> impl Iterator<Item = (usize, usize)> + '_ {
+        NEIGHBORS4.iter().filter_map(move |&(dx, dy)| {
+            let next = (
+                point.0.checked_add_signed(dx as isize)?,
+                point.1.checked_add_signed(dy as isize)?,
+            );
+            self.contains(next).then_some(next)
+        })
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = ((usize, usize), u32)> + '_ {
+        (0..self.height())
+            .flat_map(move |y| (0..self.width()).map(move |x| ((x, y), self.risk((x, y)))))
     }
 }
 
-fn get_neighbors(point: (u32, u32), map: &[Vec<u32>]) -> Vec<(u32, u32)> {
-    let mut points = vec![];
-    if point.0 > 0 {
-        points.push((point.0 - 1, point.1));
+/// Cheapest route between two points of the map tiled `tile_factor` times,
+/// or `None` if either point is off the map.
+pub fn lowest_risk(
+    map: &Grid<u32>,
+    start: (usize, usize),
+    goal: (usize, usize),
+    tile_factor: usize,
+) -> Option<Route<(usize, usize), u32>> {
+    let tiled = TiledMap::new(map, tile_factor);
+    if !tiled.contains(start) || !tiled.contains(goal) {
+        return None;
     }
-    if point.0 < (map.len() - 1) as u32 {
-        points.push((point.0 + 1, point.1));
+    astar(
+        start,
+        |&point| {
+            tiled
+                .neighbors4(point)
+                .map(move |next| (next, tiled.risk(next)))
+        },
+        // Every step costs at least 1
+        |&(x, y)| (x.abs_diff(goal.0) + y.abs_diff(goal.1)) as u32,
+        |&point| point == goal,
+    )
+}
+
+fn corner_to_corner(map: &Grid<u32>, tile_factor: usize) -> u32 {
+    let tiled = TiledMap::new(map, tile_factor);
+    let goal = (tiled.width() - 1, tiled.height() - 1);
+    lowest_risk(map, (0, 0), goal, tile_factor).unwrap().cost
+}
+
+/// Each point of the route with its risk and the total risk so far, one per
+/// line as `x,y risk total`.
+pub fn render_coords(map: &TiledMap, route: &[(usize, usize)]) -> String {
+    let mut total = 0;
+    let mut lines = vec![];
+    for (i, &point) in route.iter().enumerate() {
+        // The start isn't entered, so its risk doesn't count
+        if i > 0 {
+            total += map.risk(point);
+        }
+        lines.push(format!(
+            "{},{} {} {}",
+            point.0,
+            point.1,
+            map.risk(point),
+            total
+        ));
     }
-    if point.1 > 0 {
-        points.push((point.0, point.1 - 1));
+    lines.join("\n")
+}
+
+/// The map with every point off the route blanked out as '.'.
+pub fn render_text(map: &TiledMap, route: &[(usize, usize)]) -> String {
+    let on_route: HashSet<_> = route.iter().collect();
+    let mut out = String::new();
+    for (point, risk) in map.iter() {
+        if point.0 == 0 && point.1 > 0 {
+            out.push('\n');
+        }
+        if on_route.contains(&point) {
+            out.push(char::from_digit(risk, 10).unwrap_or('?'));
+        } else {
+            out.push('.');
+        }
     }
-    if point.1 < (map[0].len() - 1) as u32 {
-        points.push((point.0, point.1 + 1));
+    out
+}
+
+/// The whole map with the route picked out in bold red, for a terminal.
+pub fn render_terminal(map: &TiledMap, route: &[(usize, usize)]) -> String {
+    let on_route: HashSet<_> = route.iter().collect();
+    let mut out = String::new();
+    for (point, risk) in map.iter() {
+        if point.0 == 0 && point.1 > 0 {
+            out.push('\n');
+        }
+        if on_route.contains(&point) {
+            out += &format!(
+                "{}{}{}{}{}",
+                style::Bold,
+                color::Fg(color::Red),
+                risk,
+                color::Fg(color::Reset),
+                style::Reset
+            );
+        } else {
+            out += &format!("{}{}{}", style::Faint, risk, style::Reset);
+        }
     }
+    out
+}
 
-    points
+/// The map as an SVG image, darker cells being riskier, with the route drawn
+/// over it.
+pub fn render_svg(map: &TiledMap, route: &[(usize, usize)]) -> String {
+    const CELL: usize = 10;
+    let mut out = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\">\n",
+        map.width() * CELL,
+        map.height() * CELL
+    );
+    for ((x, y), risk) in map.iter() {
+        let shade = 255 - 25 * risk;
+        out += &format!(
+            "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"rgb({},{},{})\"><title>{},{}: {}</title></rect>\n",
+            x * CELL,
+            y * CELL,
+            CELL,
+            CELL,
+            shade,
+            shade,
+            shade,
+            x,
+            y,
+            risk
+        );
+    }
+    let points: Vec<_> = route
+        .iter()
+        .map(|(x, y)| format!("{},{}", x * CELL + CELL / 2, y * CELL + CELL / 2))
+        .collect();
+    out += &format!(
+        "<polyline points=\"{}\" fill=\"none\" stroke=\"red\" stroke-width=\"{}\"/>\n",
+        points.join(" "),
+        CELL / 3
+    );
+    out += "</svg>\n";
+    out
 }
 
 #[aoc(day15, part1)]
-pub fn part1(input: &[Vec<u32>]) -> u32 {
+pub fn part1(input: &Grid<u32>) -> u32 {
     // 748 is answer
-    let mut explorer = Explorer::new(input);
-    let goal = ((input.len() - 1) as u32, (input[0].len() - 1) as u32);
-    explorer.explore2(goal)
+    corner_to_corner(input, 1)
 }
 
-#[allow(clippy::needless_range_loop)]
 #[aoc(day15, part2)]
-pub fn part2(input: &[Vec<u32>]) -> u32 {
-    // Build new map
+pub fn part2(input: &Grid<u32>) -> u32 {
     // Answer: 3045
-    let rx = input.len();
-    let ry = input[0].len();
-    let mut new_input = vec![];
-    for x in 0..5 {
-        for ix in 0..rx {
-            let mut line = vec![];
-            for y in 0..5 {
-                let sumidx = x + y;
-                for iy in 0..ry {
-                    let mut value = input[ix][iy] + sumidx;
-                    while value > 9 {
-                        value -= 9;
-                    }
-                    line.push(value);
-                }
-            }
-            new_input.push(line);
-        }
-    }
-
-    // Solve new map
-    let mut explorer = Explorer::new(&new_input);
-    let goal = (
-        (new_input.len() - 1) as u32,
-        (new_input[0].len() - 1) as u32,
-    );
-    explorer.explore2(goal)
+    corner_to_corner(input, 5)
 }
 
 #[cfg(test)]
@@ -136,14 +213,57 @@ mod test {
     #[test]
     fn test_part1() {
         let input = read_to_string("input/2021/15.txt").unwrap();
-        let input = load_input(&input);
+        let input = load_input(&input).unwrap();
         assert_eq!(part1(&input), 40);
     }
 
     #[test]
     fn test_part2() {
         let input = read_to_string("input/2021/15.txt").unwrap();
-        let input = load_input(&input);
+        let input = load_input(&input).unwrap();
         assert_eq!(part2(&input), 315);
     }
+
+    #[test]
+    fn test_route() {
+        let input = read_to_string("input/2021/15.txt").unwrap();
+        let input = load_input(&input).unwrap();
+        let route = lowest_risk(&input, (0, 0), (9, 9), 1).unwrap();
+        assert_eq!(route.states.first(), Some(&(0, 0)));
+        assert_eq!(route.states.last(), Some(&(9, 9)));
+        let map = TiledMap::new(&input, 1);
+        assert!(render_coords(&map, &route.states).ends_with("9,9 1 40"));
+        let text = render_text(&map, &route.states);
+        assert_eq!(text.lines().count(), 10);
+        assert!(text.starts_with("1........."));
+
+        let tiled = TiledMap::new(&input, 5);
+        assert_eq!(tiled.risk((10, 0)), 2);
+        assert_eq!(tiled.risk((49, 49)), 9);
+        assert_eq!(tiled.risk((12, 0)), 7);
+        assert_eq!(tiled.risk((47, 0)), 2);
+
+        // Both corners have risk 1, so it costs the same both ways
+        let route = lowest_risk(&input, (9, 9), (0, 0), 1).unwrap();
+        assert_eq!(route.cost, 40);
+        let route = lowest_risk(&input, (9, 0), (10, 0), 5).unwrap();
+        assert_eq!(route.cost, 2);
+        assert!(lowest_risk(&input, (0, 0), (50, 0), 5).is_none());
+    }
+
+    #[test]
+    fn test_zero_risk() {
+        let err = load_input("190\n111\n111").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "1:3: expected a risk from 1 to 9, found \"0\""
+        );
+
+        // Built by hand rather than parsed, a 0 is a 9 and doesn't underflow
+        let map = Grid::parse_digits("190\n111\n111").unwrap();
+        let tiled = TiledMap::new(&map, 2);
+        assert_eq!(tiled.risk((2, 0)), 9);
+        assert_eq!(tiled.risk((5, 0)), 1);
+        assert_eq!(lowest_risk(&map, (0, 0), (5, 5), 2).unwrap().cost, 18);
+    }
 }
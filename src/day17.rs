@@ -1,27 +1,29 @@
+use crate::error::{parse_token, ParseError};
+
 #[derive(Debug)]
 pub struct Rectangle((i32, i32), (i32, i32));
 
+fn parse_range(idx: usize, line: &str, range: Option<&str>) -> Result<(i32, i32), ParseError> {
+    let range = range.ok_or_else(|| ParseError::missing(idx, line, "a range"))?;
+    let range = range.split(',').next().unwrap_or(range).trim_end();
+    let mut vals = range.split("..");
+    let min = parse_token(idx, line, vals.next(), "a range start")?;
+    let max = parse_token(idx, line, vals.next(), "'..'")?;
+    Ok((min, max))
+}
+
 #[aoc_generator(day17)]
-pub fn load_input(input: &str) -> Rectangle {
-    let mut segments = input.split('=');
+pub fn load_input(input: &str) -> Result<Rectangle, ParseError> {
+    let line = input
+        .lines()
+        .next()
+        .ok_or_else(|| ParseError::eof(0, "a target area"))?;
+    let mut segments = line.split('=');
     segments.next();
-    let xpart = segments.next().unwrap();
-    let ypart = segments.next().unwrap();
-    let mut xvals = xpart.split("..");
-    let mut yvals = ypart.split("..");
-    let xmin = xvals.next().unwrap();
-    let xmax = xvals.next().unwrap();
-    let mut xmax = xmax.split(',');
-    let xmax = xmax.next().unwrap();
-    let ymin = yvals.next().unwrap();
-    let ymax = yvals.next().unwrap();
-    let mut ymax = ymax.split(',');
-    let ymax = ymax.next().unwrap().split_whitespace().next().unwrap();
+    let xrange = parse_range(0, line, segments.next())?;
+    let yrange = parse_range(0, line, segments.next())?;
 
-    Rectangle(
-        (xmin.parse::<i32>().unwrap(), xmax.parse::<i32>().unwrap()),
-        (ymin.parse::<i32>().unwrap(), ymax.parse::<i32>().unwrap()),
-    )
+    Ok(Rectangle(xrange, yrange))
 }
 
 #[allow(clippy::comparison_chain)]
@@ -88,14 +90,14 @@ mod test {
     #[test]
     fn test_part1() {
         let input = read_to_string("input/2021/17.txt").unwrap();
-        let input = load_input(&input);
+        let input = load_input(&input).unwrap();
         assert_eq!(part1(&input), 45);
     }
 
     #[test]
     fn test_part2() {
         let input = read_to_string("input/2021/17.txt").unwrap();
-        let input = load_input(&input);
+        let input = load_input(&input).unwrap();
         assert_eq!(part2(&input), 112);
     }
 }
@@ -1,8 +1,9 @@
+use crate::error::{parse_digit, ParseError};
 use itertools::Itertools;
 use std::fmt;
 
 #[derive(Clone, Copy, PartialEq, Eq)]
-enum FishChar {
+pub enum FishChar {
     Left,
     Right,
     Value(u32),
@@ -21,25 +22,34 @@ impl fmt::Debug for FishChar {
 }
 
 #[aoc_generator(day18)]
-fn load_input(input: &str) -> Vec<Vec<FishChar>> {
+pub fn load_input(input: &str) -> Result<Vec<Vec<FishChar>>, ParseError> {
     let mut output = vec![];
-    for line in input.lines() {
+    for (idx, line) in input.lines().enumerate() {
         let mut temp_line = vec![];
-        for c in line.chars() {
+        let mut depth = 0;
+        for (col, c) in line.chars().enumerate() {
             if c == '[' {
+                depth += 1;
                 temp_line.push(FishChar::Left);
             } else if c == ']' {
+                if depth == 0 {
+                    return Err(ParseError::invalid_char(idx, col, c, "a matching '['"));
+                }
+                depth -= 1;
                 temp_line.push(FishChar::Right);
             } else if c == ',' {
                 temp_line.push(FishChar::Comma);
             } else {
-                let value = c.to_digit(10).unwrap();
+                let value = parse_digit(idx, col, c, 10)?;
                 temp_line.push(FishChar::Value(value));
             }
         }
+        if depth != 0 {
+            return Err(ParseError::missing(idx, line, "']'"));
+        }
         output.push(temp_line);
     }
-    output
+    Ok(output)
 }
 
 fn reduce(fishline: &[FishChar]) -> Vec<FishChar> {
@@ -199,7 +209,7 @@ fn add_mag(left: &[FishChar], right: &[FishChar]) -> u32 {
 }
 
 #[aoc(day18, part1)]
-fn part1(input: &[Vec<FishChar>]) -> u32 {
+pub fn part1(input: &[Vec<FishChar>]) -> u32 {
     let input_clone = input.to_vec();
     let mut accumulator = input[0].clone();
     for line in input_clone.iter().skip(1) {
@@ -210,7 +220,7 @@ fn part1(input: &[Vec<FishChar>]) -> u32 {
 }
 
 #[aoc(day18, part2)]
-fn part2(input: &[Vec<FishChar>]) -> u32 {
+pub fn part2(input: &[Vec<FishChar>]) -> u32 {
     input
         .iter()
         .permutations(2)
@@ -227,14 +237,14 @@ mod test {
     #[test]
     fn test_part1() {
         let input = read_to_string("input/2021/18.txt").unwrap();
-        let input = load_input(&input);
+        let input = load_input(&input).unwrap();
         assert_eq!(part1(&input), 4140);
     }
 
     #[test]
     fn test_part2() {
         let input = read_to_string("input/2021/18.txt").unwrap();
-        let input = load_input(&input);
+        let input = load_input(&input).unwrap();
         assert_eq!(part2(&input), 3993);
     }
 }
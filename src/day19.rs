@@ -1,15 +1,15 @@
+use crate::error::{parse_token, ParseError};
 use std::collections::HashSet;
 
 #[derive(Clone, Debug)]
-struct Scanner {
+pub struct Scanner {
     beacons: HashSet<(i32, i32, i32)>,
 }
 
 #[aoc_generator(day19)]
-fn load_input(input: &str) -> Vec<Scanner> {
-    let mut output = vec![];
-    let mut cntr = 0;
-    for line in input.lines() {
+pub fn load_input(input: &str) -> Result<Vec<Scanner>, ParseError> {
+    let mut output: Vec<Scanner> = vec![];
+    for (idx, line) in input.lines().enumerate() {
         if line.contains("scanner") {
             // New Scanner
             output.push(Scanner {
@@ -18,16 +18,24 @@ fn load_input(input: &str) -> Vec<Scanner> {
         } else if line.contains(',') {
             // If position
             let mut liter = line.split(',');
-            let n1 = liter.next().unwrap().parse::<i32>().unwrap();
-            let n2 = liter.next().unwrap().parse::<i32>().unwrap();
-            let n3 = liter.next().unwrap().parse::<i32>().unwrap();
+            let n1 = parse_token(idx, line, liter.next(), "an x coordinate")?;
+            let n2 = parse_token(idx, line, liter.next(), "a y coordinate")?;
+            let n3 = parse_token(idx, line, liter.next(), "a z coordinate")?;
             let pos = (n1, n2, n3);
-            output[cntr].beacons.insert(pos);
-        } else if line.is_empty() {
-            cntr += 1;
+            match output.last_mut() {
+                Some(scanner) => scanner.beacons.insert(pos),
+                None => return Err(ParseError::invalid(idx, line, line, "a scanner header")),
+            };
+        } else if !line.is_empty() {
+            return Err(ParseError::invalid(
+                idx,
+                line,
+                line,
+                "a scanner header or beacon",
+            ));
         }
     }
-    output
+    Ok(output)
 }
 
 // List the 24 orientations of this given one of them
@@ -139,7 +147,7 @@ fn check_set(
 }
 
 #[aoc(day19, part1)]
-fn part1(input: &[Scanner]) -> usize {
+pub fn part1(input: &[Scanner]) -> usize {
     let mut absmap = HashSet::<(i32, i32, i32)>::new();
 
     // First get our absolute position established
@@ -197,7 +205,7 @@ fn part1(input: &[Scanner]) -> usize {
 }
 
 #[aoc(day19, part2)]
-fn part2(input: &[Scanner]) -> i32 {
+pub fn part2(input: &[Scanner]) -> i32 {
     let mut absmap = HashSet::<(i32, i32, i32)>::new();
 
     // First get our absolute position established
@@ -278,14 +286,14 @@ mod test {
     #[test]
     fn test_part1() {
         let input = read_to_string("input/2021/19.txt").unwrap();
-        let input = load_input(&input);
+        let input = load_input(&input).unwrap();
         assert_eq!(part1(&input), 79);
     }
 
     #[test]
     fn test_part2() {
         let input = read_to_string("input/2021/19.txt").unwrap();
-        let input = load_input(&input);
+        let input = load_input(&input).unwrap();
         assert_eq!(part2(&input), 3621);
     }
 }
@@ -1,3 +1,5 @@
+use crate::error::{next_token, parse_token, ParseError};
+
 #[derive(Debug, PartialEq, Eq)]
 pub enum Direction {
     Forward,
@@ -12,21 +14,29 @@ pub struct Command {
 }
 
 #[aoc_generator(day2)]
-pub fn load_input(input: &str) -> Vec<Command> {
+pub fn load_input(input: &str) -> Result<Vec<Command>, ParseError> {
     let mut output = vec![];
-    for line in input.lines() {
-        let cmds: Vec<&str> = line.split(' ').take(2).collect();
-        let mut dir = Direction::Up;
-        if cmds[0] == "forward" {
-            dir = Direction::Forward;
-        } else if cmds[0] == "down" {
-            dir = Direction::Down;
-        }
+    for (idx, line) in input.lines().enumerate() {
+        let mut cmds = line.split(' ');
+        let dirstr = next_token(idx, line, &mut cmds, "a direction")?;
+        let dir = match dirstr {
+            "forward" => Direction::Forward,
+            "down" => Direction::Down,
+            "up" => Direction::Up,
+            _ => {
+                return Err(ParseError::invalid(
+                    idx,
+                    line,
+                    dirstr,
+                    "forward, down or up",
+                ))
+            }
+        };
 
-        let num = cmds[1].parse().unwrap();
+        let num = parse_token(idx, line, cmds.next(), "a distance")?;
         output.push(Command { dir, num });
     }
-    output
+    Ok(output)
 }
 
 #[aoc(day2, part1)]
@@ -73,14 +83,29 @@ mod test {
     #[test]
     fn test_part1() {
         let input = read_to_string("input/2021/02.txt").unwrap();
-        let input = load_input(&input);
+        let input = load_input(&input).unwrap();
         assert_eq!(part1(&input), 150);
     }
 
     #[test]
     fn test_part2() {
         let input = read_to_string("input/2021/02.txt").unwrap();
-        let input = load_input(&input);
+        let input = load_input(&input).unwrap();
         assert_eq!(part2(&input), 900);
     }
+
+    #[test]
+    fn test_bad_input() {
+        let err = load_input("forward 5\nsideways 3\n").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "2:1: expected forward, down or up, found \"sideways\""
+        );
+
+        let err = load_input("down\n").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "1:5: expected a distance, found end of line"
+        );
+    }
 }
@@ -1,201 +1,107 @@
-use std::collections::HashMap;
+use crate::error::ParseError;
+use crate::grid::{Edge, Grid};
 
 #[aoc_generator(day20)]
-fn load_input(input: &str) -> (Vec<u8>, HashMap<(i32, i32), u8>) {
+pub fn load_input(input: &str) -> Result<(Vec<u8>, Grid<u8>), ParseError> {
     let mut cntr = 0;
     let mut key = vec![];
-    for line in input.lines().take_while(|line| !line.is_empty()) {
-        for c in line.chars() {
-            if c == '.' {
+    for (idx, line) in input
+        .lines()
+        .enumerate()
+        .take_while(|(_, line)| !line.is_empty())
+    {
+        for (col, (at, c)) in line.char_indices().enumerate() {
+            if key.len() == 512 {
+                return Err(ParseError::invalid(
+                    idx,
+                    line,
+                    &line[at..],
+                    "a 512 character enhancement key",
+                ));
+            } else if c == '.' {
                 key.push(0);
             } else if c == '#' {
                 key.push(1);
             } else {
-                panic!("Invalid key character");
+                return Err(ParseError::invalid_char(idx, col, c, "'.' or '#'"));
             }
         }
         cntr += 1;
     }
-
-    let mut image = HashMap::new();
-    for (y, line) in input.lines().skip(cntr + 1).enumerate() {
-        for (x, c) in line.chars().enumerate() {
-            if c == '#' {
-                image.insert((x as i32, y as i32), 1);
-            } else if c == '.' {
-                image.insert((x as i32, y as i32), 0);
-            } else {
-                panic!("Invalid input!");
-            }
-        }
+    if key.len() != 512 {
+        let expected = "a 512 character enhancement key";
+        return Err(match cntr {
+            0 => ParseError::eof(0, expected),
+            _ => ParseError::missing(cntr - 1, input.lines().nth(cntr - 1).unwrap(), expected),
+        });
     }
 
-    (key, image)
-}
+    let image: Vec<_> = input.lines().skip(cntr + 1).collect();
+    let image = Grid::parse(&image.join("\n"), "'.' or '#'", |c| match c {
+        '.' => Some(0),
+        '#' => Some(1),
+        _ => None,
+    })
+    .map_err(|e| e.shift(cntr + 1))?;
 
-// Get the surrounding point indexes
-fn get_neighbors(point: (i32, i32)) -> Vec<(i32, i32)> {
-    vec![
-        (point.0 - 1, point.1 - 1),
-        (point.0, point.1 - 1),
-        (point.0 + 1, point.1 - 1),
-        (point.0 - 1, point.1),
-        (point.0, point.1),
-        (point.0 + 1, point.1),
-        (point.0 - 1, point.1 + 1),
-        (point.0, point.1 + 1),
-        (point.0 + 1, point.1 + 1),
-    ]
+    Ok((key, image.with_edge(Edge::Infinite(0))))
 }
 
 fn code_to_number(code: &[u8]) -> usize {
     let mut output = code[0] as usize;
-    for c in code.to_vec().iter().skip(1) {
+    for c in code.iter().skip(1) {
         output <<= 1;
         output += *c as usize;
     }
     output
 }
 
-fn enhance_pixel(pixel: (i32, i32), key: &[u8], map: &HashMap<(i32, i32), u8>, toggle: u8) -> u8 {
-    let idxs = get_neighbors(pixel);
+fn enhance_pixel(pixel: (i64, i64), key: &[u8], map: &Grid<u8>) -> u8 {
     let mut code = vec![];
-    for i in idxs {
-        if let Some(value) = map.get(&i) {
-            code.push(*value);
-        } else {
-            code.push(toggle);
+    for dy in -1..=1 {
+        for dx in -1..=1 {
+            code.push(*map.get(pixel.0 + dx, pixel.1 + dy).unwrap());
         }
     }
     let number = code_to_number(&code);
     key[number]
 }
 
+// One pixel of the infinite background on each side can change per step, so
+// grow the image by that much. The background itself all flips together.
+fn enhance(map: &Grid<u8>, key: &[u8]) -> Grid<u8> {
+    let outside = match map.edge() {
+        Edge::Infinite(0) => key[0],
+        _ => key[511],
+    };
+    Grid::from_fn(map.width() + 2, map.height() + 2, |x, y| {
+        enhance_pixel((x as i64 - 1, y as i64 - 1), key, map)
+    })
+    .with_edge(Edge::Infinite(outside))
+}
+
 #[allow(dead_code)]
-fn print_board(map: &HashMap<(i32, i32), u8>) {
-    for y in -40..50 {
-        for x in -40..50 {
-            if let Some(value) = map.get(&(x, y)) {
-                if *value == 0 {
-                    print!(".");
-                } else {
-                    print!("#");
-                }
-            } else {
-                print!(".");
-            }
-        }
-        println!();
-    }
-    println!();
-    println!();
+fn print_board(map: &Grid<u8>) {
+    println!("{}", map.render(|&v| if v == 0 { '.' } else { '#' }));
     println!();
 }
 
-fn add_border(map: &mut HashMap<(i32, i32), u8>, n_times: i32) {
-    // First let's insert a border of darkness around the existing image
-    let upper_left = (0, 0);
-    let mut lower_right = (0, 0);
-    let mut prior_sum = 0;
-    for pixel in map.keys() {
-        if pixel.0 + pixel.1 > prior_sum {
-            prior_sum = pixel.0 + pixel.1;
-            lower_right = *pixel;
-        }
-    }
-
-    let n = n_times + 1;
-    for x in upper_left.0 - n..lower_right.0 + n {
-        for k in 1..n + 1 {
-            map.insert((x, upper_left.1 - k), 0);
-            map.insert((x, lower_right.1 + k), 0);
-        }
-    }
-
-    for y in upper_left.1 - n..lower_right.1 + n {
-        for k in 1..n + 1 {
-            map.insert((upper_left.0 - k, y), 0);
-            map.insert((lower_right.0 + k, y), 0);
-        }
+fn count_lit(input: &(Vec<u8>, Grid<u8>), steps: usize) -> usize {
+    let mut image = input.1.clone();
+    for _ in 0..steps {
+        image = enhance(&image, &input.0);
     }
+    image.values().filter(|&&v| v == 1).count()
 }
 
 #[aoc(day20, part1)]
-fn part1(input: &(Vec<u8>, HashMap<(i32, i32), u8>)) -> usize {
-    let mut old_image = input.1.clone();
-    let mut new_image = HashMap::new();
-
-    add_border(&mut old_image, 2);
-
-    let mut toggling = true;
-    if input.0[0] == 0 {
-        toggling = false;
-    }
-
-    // Now iterate on stuff in the map
-    let mut toggle = 0;
-    for _ in 0..2 {
-        //print_board(&old_image);
-        for pixel in old_image.keys() {
-            let new_pixel = enhance_pixel(*pixel, &input.0, &old_image, toggle);
-            new_image.insert(*pixel, new_pixel);
-        }
-        old_image = new_image;
-        new_image = HashMap::new();
-
-        if toggling {
-            toggle ^= 1;
-        }
-    }
-
-    // Count up the '#'s in the new image
-    //print_board(&old_image);
-    let mut answer = 0;
-    for value in old_image.values() {
-        if *value == 1 {
-            answer += 1;
-        }
-    }
-    answer
+pub fn part1(input: &(Vec<u8>, Grid<u8>)) -> usize {
+    count_lit(input, 2)
 }
 
 #[aoc(day20, part2)]
-fn part2(input: &(Vec<u8>, HashMap<(i32, i32), u8>)) -> usize {
-    let mut old_image = input.1.clone();
-    let mut new_image = HashMap::new();
-
-    add_border(&mut old_image, 50);
-
-    let mut toggling = true;
-    if input.0[0] == 0 {
-        toggling = false;
-    }
-
-    // Now iterate on stuff in the map
-    let mut toggle = 0;
-    for _ in 0..50 {
-        //print_board(&old_image);
-        for pixel in old_image.keys() {
-            let new_pixel = enhance_pixel(*pixel, &input.0, &old_image, toggle);
-            new_image.insert(*pixel, new_pixel);
-        }
-        old_image = new_image;
-        new_image = HashMap::new();
-
-        if toggling {
-            toggle ^= 1;
-        }
-    }
-
-    // Count up the '#'s in the new image
-    //print_board(&old_image);
-    let mut answer = 0;
-    for value in old_image.values() {
-        if *value == 1 {
-            answer += 1;
-        }
-    }
-    answer
+pub fn part2(input: &(Vec<u8>, Grid<u8>)) -> usize {
+    count_lit(input, 50)
 }
 
 #[cfg(test)]
@@ -206,14 +112,35 @@ mod test {
     #[test]
     fn test_part1() {
         let input = read_to_string("input/2021/20.txt").unwrap();
-        let input = load_input(&input);
+        let input = load_input(&input).unwrap();
         assert_eq!(part1(&input), 35);
     }
 
     #[test]
     fn test_part2() {
         let input = read_to_string("input/2021/20.txt").unwrap();
-        let input = load_input(&input);
+        let input = load_input(&input).unwrap();
         assert_eq!(part2(&input), 3351);
     }
+
+    #[test]
+    fn test_bad_key() {
+        let input = format!("{}\n\n#.\n.#", ".".repeat(600));
+        assert_eq!(
+            load_input(&input).unwrap_err().to_string(),
+            format!(
+                "1:513: expected a 512 character enhancement key, found {:?}",
+                ".".repeat(88)
+            )
+        );
+        let input = format!("{}\n\n#.\n.#", ".".repeat(300));
+        assert_eq!(
+            load_input(&input).unwrap_err().to_string(),
+            "1:301: expected a 512 character enhancement key, found end of line"
+        );
+        assert_eq!(
+            load_input("").unwrap_err().to_string(),
+            "1: expected a 512 character enhancement key, found end of input"
+        );
+    }
 }
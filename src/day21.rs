@@ -1,518 +1,378 @@
-use std::collections::HashMap;
+use crate::error::{parse_token, ParseError};
+use num_bigint::BigUint;
 
 #[aoc_generator(day21)]
-fn load_input(input: &str) -> Vec<Player> {
+pub fn load_input(input: &str) -> Result<Vec<Player>, ParseError> {
     let mut output = vec![];
-    for line in input.lines() {
+    for (idx, line) in input.lines().enumerate() {
+        if output.len() == 2 {
+            return Err(ParseError::invalid(idx, line, line, "only two players"));
+        }
         let mut liter = line.split(": ");
         liter.next();
-        let pos = liter.next().unwrap().parse::<usize>().unwrap();
+        let pos = parse_token(idx, line, liter.next(), "a starting position")?;
+        if !(1..=10).contains(&pos) {
+            let token = line.rsplit(' ').next().unwrap_or(line);
+            return Err(ParseError::invalid(
+                idx,
+                line,
+                token,
+                "a position from 1 to 10",
+            ));
+        }
         output.push(Player::new(pos));
     }
-    output
+    if output.len() < 2 {
+        return Err(ParseError::eof(output.len(), "two players"));
+    }
+    Ok(output)
+}
+
+/// Something the players roll to move.
+pub trait Dice {
+    /// Every face the next roll could show, each with the number of
+    /// universes it shows up in.
+    fn outcomes(&mut self) -> Vec<(usize, u64)>;
+}
+
+/// Dice that only ever land one way, which are the only ones a game can
+/// actually be played with.
+pub trait Roll {
+    fn roll(&mut self) -> usize;
+}
+
+impl<R: Roll> Dice for R {
+    fn outcomes(&mut self) -> Vec<(usize, u64)> {
+        vec![(self.roll(), 1)]
+    }
 }
 
-struct DeterministicDice {
-    value: usize,
-    n_rolls: usize,
+/// Counts up from 1 to `faces` and starts over.
+#[derive(Clone, Debug)]
+pub struct DeterministicDice {
+    next: usize,
+    faces: usize,
 }
 
 impl DeterministicDice {
-    fn new() -> DeterministicDice {
-        DeterministicDice {
-            value: 1,
-            n_rolls: 0,
-        }
+    /// The d100 from part 1.
+    pub fn new() -> DeterministicDice {
+        DeterministicDice::with_faces(100)
+    }
+
+    pub fn with_faces(faces: usize) -> DeterministicDice {
+        DeterministicDice { next: 1, faces }
+    }
+}
+
+impl Default for DeterministicDice {
+    fn default() -> DeterministicDice {
+        DeterministicDice::new()
     }
 }
 
-impl Iterator for DeterministicDice {
-    type Item = usize;
-    fn next(&mut self) -> Option<Self::Item> {
-        let output = self.value;
-        self.value += 1;
-        if self.value > 100 {
-            self.value = 1;
+impl Roll for DeterministicDice {
+    fn roll(&mut self) -> usize {
+        let face = self.next;
+        self.next = face % self.faces + 1;
+        face
+    }
+}
+
+/// Lands on every face at once, one universe each.
+#[derive(Clone, Copy, Debug)]
+pub struct DiracDice {
+    pub faces: usize,
+}
+
+impl Dice for DiracDice {
+    fn outcomes(&mut self) -> Vec<(usize, u64)> {
+        (1..=self.faces).map(|face| (face, 1)).collect()
+    }
+}
+
+/// Lands on a pseudo-random face, the same sequence every time for the same
+/// seed.
+#[derive(Clone, Debug)]
+pub struct RandomDice {
+    state: u64,
+    faces: usize,
+}
+
+impl RandomDice {
+    pub fn new(seed: u64, faces: usize) -> RandomDice {
+        // Xorshift never leaves 0, so the one seed that would land there
+        // starts somewhere else instead
+        let state = match seed ^ 0x9e37_79b9_7f4a_7c15 {
+            0 => 0x2545_f491_4f6c_dd1d,
+            state => state,
+        };
+        RandomDice { state, faces }
+    }
+}
+
+impl Roll for RandomDice {
+    fn roll(&mut self) -> usize {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        (self.state % self.faces as u64) as usize + 1
+    }
+}
+
+/// How a game of ordinary dice ended.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Outcome {
+    pub winner: usize,
+    pub scores: Vec<usize>,
+    pub rolls: usize,
+}
+
+impl Outcome {
+    /// Best score of everybody who didn't win, which with two players is
+    /// just the loser's.
+    pub fn losing_score(&self) -> usize {
+        self.scores
+            .iter()
+            .enumerate()
+            .filter(|&(i, _)| i != self.winner)
+            .map(|(_, &score)| score)
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+/// Play on a board of `board` spaces, `rolls` rolls a turn, until somebody
+/// reaches `target`. Part 1 is a board of 10 and three rolls.
+pub fn simulate(
+    players: &[Player],
+    dice: &mut impl Roll,
+    board: usize,
+    rolls: usize,
+    target: usize,
+) -> Outcome {
+    assert!(!players.is_empty(), "nobody to play");
+    assert!(board > 0, "the board needs a space");
+    let mut players = players.to_vec();
+    let mut rolled = 0;
+    for turn in (0..players.len()).cycle() {
+        let player = &mut players[turn];
+        let moves: usize = (0..rolls).map(|_| dice.roll()).sum();
+        rolled += rolls;
+        player.position = (player.position + moves - 1) % board + 1;
+        player.score += player.position;
+        if player.score >= target {
+            return Outcome {
+                winner: turn,
+                scores: players.iter().map(|p| p.score).collect(),
+                rolls: rolled,
+            };
         }
-        self.n_rolls += 1;
-        Some(output)
     }
+    unreachable!("somebody always gets a turn")
+}
+
+/// Share of `games` each player wins, which for a fair die should come close
+/// to `DiracGame::win_chances` for a game with the same board, target, faces
+/// and rolls.
+pub fn estimate_wins(
+    players: &[Player],
+    dice: &mut impl Roll,
+    game: &DiracGame,
+    games: usize,
+) -> Vec<f64> {
+    let mut wins = vec![0; players.len()];
+    for _ in 0..games {
+        wins[simulate(players, dice, game.board, game.rolls, game.target).winner] += 1;
+    }
+    wins.iter().map(|&w| w as f64 / games as f64).collect()
 }
 
 #[derive(Clone, Copy, Debug)]
-struct Player {
+pub struct Player {
     score: usize,
     position: usize,
 }
 
 impl Player {
-    fn new(position: usize) -> Player {
+    pub fn new(position: usize) -> Player {
         Player { score: 0, position }
     }
 }
 
 #[aoc(day21, part1)]
-fn part1(input: &[Player]) -> usize {
-    let mut dice = DeterministicDice::new();
-    let mut p1 = input[0];
-    let mut p2 = input[1];
-
-    loop {
-        let r1 = dice.next().unwrap();
-        let r2 = dice.next().unwrap();
-        let r3 = dice.next().unwrap();
-        let r = r1 + r2 + r3;
-        p1.position += r;
-        while p1.position > 10 {
-            p1.position -= 10;
-        }
-        p1.score += p1.position;
-        if p1.score >= 1000 {
-            return p2.score * dice.n_rolls;
-        }
+pub fn part1(input: &[Player]) -> usize {
+    let outcome = simulate(input, &mut DeterministicDice::new(), 10, 3, 1000);
+    outcome.losing_score() * outcome.rolls
+}
 
-        let r1 = dice.next().unwrap();
-        let r2 = dice.next().unwrap();
-        let r3 = dice.next().unwrap();
-        let r = r1 + r2 + r3;
-        p2.position += r;
-        while p2.position > 10 {
-            p2.position -= 10;
-        }
-        p2.score += p2.position;
-        if p2.score >= 1000 {
-            return p1.score * dice.n_rolls;
-        }
+/// A game of Dirac Dice, where every roll of the die splits the universe in
+/// `faces`, one for each way it could land.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DiracGame {
+    /// Spaces around the board, numbered from 1.
+    pub board: usize,
+    /// Score that wins the game.
+    pub target: usize,
+    pub faces: usize,
+    /// Rolls per turn, all added together.
+    pub rolls: usize,
+}
+
+/// Anything universes can be counted in. `Default` has to be zero.
+trait Count: Clone + Default {
+    fn one() -> Self;
+    /// What a turn total that `ways` of the `of` possible rolls add up to
+    /// counts for.
+    fn weight(ways: u64, of: u64) -> Self;
+    /// Add `other` `times` over, or `None` if that doesn't fit.
+    fn add_times(&mut self, other: &Self, times: &Self) -> Option<()>;
+}
+
+impl Count for u128 {
+    fn one() -> u128 {
+        1
+    }
+
+    fn weight(ways: u64, _: u64) -> u128 {
+        ways as u128
+    }
+
+    fn add_times(&mut self, other: &u128, times: &u128) -> Option<()> {
+        *self = self.checked_add(other.checked_mul(*times)?)?;
+        Some(())
     }
 }
 
-#[derive(Clone, Copy, Debug)]
-struct State {
-    score: usize,
-    nways: usize,
+impl Count for BigUint {
+    fn one() -> BigUint {
+        BigUint::from(1u8)
+    }
+
+    fn weight(ways: u64, _: u64) -> BigUint {
+        BigUint::from(ways)
+    }
+
+    fn add_times(&mut self, other: &BigUint, times: &BigUint) -> Option<()> {
+        *self += other * times;
+        Some(())
+    }
+}
+
+/// Chances rather than universes, every roll being equally likely.
+impl Count for f64 {
+    fn one() -> f64 {
+        1.0
+    }
+
+    fn weight(ways: u64, of: u64) -> f64 {
+        ways as f64 / of as f64
+    }
+
+    fn add_times(&mut self, other: &f64, times: &f64) -> Option<()> {
+        *self += other * times;
+        Some(())
+    }
 }
 
-impl State {
-    fn new(pos0: usize, key: &[usize]) -> State {
-        let roll_nways: [usize; 7] = [1, 3, 6, 7, 6, 3, 1];
-        let mut score = 0;
-        let mut position = pos0;
-        let mut nways = 1;
-        for s in key {
-            position += s;
-            if position > 10 {
-                position -= 10;
+impl DiracGame {
+    pub const PART2: DiracGame = DiracGame {
+        board: 10,
+        target: 21,
+        faces: 3,
+        rolls: 3,
+    };
+
+    /// How many ways the rolls of one turn can add up to each total.
+    fn totals(&self) -> Vec<(usize, u64)> {
+        let faces = DiracDice { faces: self.faces }.outcomes();
+        let mut ways = vec![1u64];
+        for _ in 0..self.rolls {
+            let mut next = vec![0; ways.len() + self.faces];
+            for (sum, &n) in ways.iter().enumerate() {
+                for &(face, universes) in &faces {
+                    next[sum + face] += n * universes;
+                }
             }
-            score += position;
-            nways *= roll_nways[s - 3];
+            ways = next;
         }
-        State { score, nways }
+        ways.into_iter()
+            .enumerate()
+            .filter(|&(_, n)| n > 0)
+            .collect()
     }
-}
 
-#[allow(clippy::type_complexity)]
-fn initialize_maps() -> (
-    HashMap<[usize; 1], State>,
-    HashMap<[usize; 2], State>,
-    HashMap<[usize; 3], State>,
-    HashMap<[usize; 4], State>,
-    HashMap<[usize; 5], State>,
-    HashMap<[usize; 6], State>,
-    HashMap<[usize; 7], State>,
-    HashMap<[usize; 8], State>,
-    HashMap<[usize; 9], State>,
-    HashMap<[usize; 10], State>,
-) {
-    let state1: HashMap<[usize; 1], State> = HashMap::new();
-    let state2: HashMap<[usize; 2], State> = HashMap::new();
-    let state3: HashMap<[usize; 3], State> = HashMap::new();
-    let state4: HashMap<[usize; 4], State> = HashMap::new();
-    let state5: HashMap<[usize; 5], State> = HashMap::new();
-    let state6: HashMap<[usize; 6], State> = HashMap::new();
-    let state7: HashMap<[usize; 7], State> = HashMap::new();
-    let state8: HashMap<[usize; 8], State> = HashMap::new();
-    let state9: HashMap<[usize; 9], State> = HashMap::new();
-    let state10: HashMap<[usize; 10], State> = HashMap::new();
-
-    (
-        state1, state2, state3, state4, state5, state6, state7, state8, state9, state10,
-    )
-}
+    /// Universes in which each player wins, starting from `start` with
+    /// player 1 to move. `None` if there are too many to fit in a `u128`.
+    pub fn wins(&self, start: [usize; 2]) -> Option<[u128; 2]> {
+        self.count(start)
+    }
 
-#[allow(clippy::type_complexity)]
-fn the_dirty_work(
-    maps: &mut (
-        HashMap<[usize; 1], State>,
-        HashMap<[usize; 2], State>,
-        HashMap<[usize; 3], State>,
-        HashMap<[usize; 4], State>,
-        HashMap<[usize; 5], State>,
-        HashMap<[usize; 6], State>,
-        HashMap<[usize; 7], State>,
-        HashMap<[usize; 8], State>,
-        HashMap<[usize; 9], State>,
-        HashMap<[usize; 10], State>,
-    ),
-    pos0: usize,
-) {
-    let rolls: [usize; 7] = [3, 4, 5, 6, 7, 8, 9];
-    for roll1 in rolls {
-        let key = [roll1];
-        let level1 = State::new(pos0, &key);
-        maps.0.insert(key, level1);
-
-        if level1.score < 21 {
-            for roll2 in rolls {
-                let key = [roll1, roll2];
-                let level2 = State::new(pos0, &key);
-                maps.1.insert(key, level2);
-
-                if level2.score < 21 {
-                    for roll3 in rolls {
-                        let key = [roll1, roll2, roll3];
-                        let level3 = State::new(pos0, &key);
-                        maps.2.insert(key, level3);
-
-                        if level3.score < 21 {
-                            for roll4 in rolls {
-                                let key = [roll1, roll2, roll3, roll4];
-                                let level4 = State::new(pos0, &key);
-                                maps.3.insert(key, level4);
-
-                                if level4.score < 21 {
-                                    for roll5 in rolls {
-                                        let key = [roll1, roll2, roll3, roll4, roll5];
-                                        let level5 = State::new(pos0, &key);
-                                        maps.4.insert(key, level5);
-
-                                        if level5.score < 21 {
-                                            for roll6 in rolls {
-                                                let key =
-                                                    [roll1, roll2, roll3, roll4, roll5, roll6];
-                                                let level6 = State::new(pos0, &key);
-                                                maps.5.insert(key, level6);
-
-                                                if level6.score < 21 {
-                                                    for roll7 in rolls {
-                                                        let key = [
-                                                            roll1, roll2, roll3, roll4, roll5,
-                                                            roll6, roll7,
-                                                        ];
-                                                        let level7 = State::new(pos0, &key);
-                                                        maps.6.insert(key, level7);
-
-                                                        if level7.score < 21 {
-                                                            for roll8 in rolls {
-                                                                let key = [
-                                                                    roll1, roll2, roll3, roll4,
-                                                                    roll5, roll6, roll7, roll8,
-                                                                ];
-                                                                let level8 = State::new(pos0, &key);
-                                                                maps.7.insert(key, level8);
-
-                                                                if level8.score < 21 {
-                                                                    for roll9 in rolls {
-                                                                        let key = [
-                                                                            roll1, roll2, roll3,
-                                                                            roll4, roll5, roll6,
-                                                                            roll7, roll8, roll9,
-                                                                        ];
-                                                                        let level9 =
-                                                                            State::new(pos0, &key);
-                                                                        maps.8.insert(key, level9);
-
-                                                                        if level9.score < 21 {
-                                                                            for roll10 in rolls {
-                                                                                let key = [
-                                                                                    roll1, roll2,
-                                                                                    roll3, roll4,
-                                                                                    roll5, roll6,
-                                                                                    roll7, roll8,
-                                                                                    roll9, roll10,
-                                                                                ];
-                                                                                let level10 =
-                                                                                    State::new(
-                                                                                        pos0, &key,
-                                                                                    );
-                                                                                maps.9.insert(
-                                                                                    key, level10,
-                                                                                );
-
-                                                                                if level10.score
-                                                                                    < 21
-                                                                                {
-                                                                                    panic!(
-                                                                                        "WTF mate"
-                                                                                    );
-                                                                                }
-                                                                            }
-                                                                        }
-                                                                    }
-                                                                }
-                                                            }
-                                                        }
-                                                    }
-                                                }
-                                            }
-                                        }
-                                    }
-                                }
+    /// Same as `wins` but never runs out of bits.
+    pub fn big_wins(&self, start: [usize; 2]) -> [BigUint; 2] {
+        self.count(start).unwrap()
+    }
+
+    /// Chance of each player winning when every roll is fair. This isn't the
+    /// share of universes they win, since universes where the game ends
+    /// early split fewer times and each stands for more of the odds.
+    pub fn win_chances(&self, start: [usize; 2]) -> [f64; 2] {
+        self.count(start).unwrap()
+    }
+
+    /// Fills in a table over (position, score) of the player about to move
+    /// and the other player, which covers whose turn it is as well. Every
+    /// turn raises the total score, so going from high totals down to low
+    /// ones means every state it leads to is already done.
+    fn count<T: Count>(&self, start: [usize; 2]) -> Option<[T; 2]> {
+        assert!(
+            start.iter().all(|p| (1..=self.board).contains(p)),
+            "players have to start on the board"
+        );
+        assert!(self.target > 0, "the target score has to be above 0");
+        let (board, target) = (self.board, self.target);
+        let totals = self.totals();
+        let all = totals.iter().map(|&(_, ways)| ways).sum();
+        let totals: Vec<(usize, T)> = totals
+            .into_iter()
+            .map(|(total, ways)| (total, T::weight(ways, all)))
+            .collect();
+        let idx =
+            |p: usize, s: usize, q: usize, t: usize| ((p * target + s) * board + q) * target + t;
+        let mut table = vec![[T::default(), T::default()]; board * board * target * target];
+
+        for sum in (0..2 * target - 1).rev() {
+            for mine in sum.saturating_sub(target - 1)..target.min(sum + 1) {
+                let theirs = sum - mine;
+                for p in 0..board {
+                    for q in 0..board {
+                        let mut wins = [T::default(), T::default()];
+                        for (total, ways) in &totals {
+                            let moved = (p + total) % board;
+                            let score = mine + moved + 1;
+                            if score >= target {
+                                wins[0].add_times(&T::one(), ways)?;
+                            } else {
+                                // Now it's their turn, so the roles swap
+                                let next = &table[idx(q, theirs, moved, score)];
+                                wins[0].add_times(&next[1], ways)?;
+                                wins[1].add_times(&next[0], ways)?;
                             }
                         }
+                        table[idx(p, mine, q, theirs)] = wins;
                     }
                 }
             }
         }
+        Some(table.swap_remove(idx(start[0] - 1, 0, start[1] - 1, 0)))
     }
 }
 
 #[aoc(day21, part2)]
-fn part2(input: &[Player]) -> usize {
-    let pos0 = input[0].position;
-    let pos1 = input[1].position;
-
-    let mut maps_p1 = initialize_maps();
-    let mut maps_p2 = initialize_maps();
-
-    the_dirty_work(&mut maps_p1, pos0);
-    the_dirty_work(&mut maps_p2, pos1);
-
-    let ways_to_lose_2: usize = maps_p2
-        .1
-        .values()
-        .filter(|s| s.score < 21)
-        .map(|s| s.nways)
-        .sum();
-
-    // Wins start happening in 3 steps, stop happening after 10th step
-    let ways_to_win_3: usize = maps_p1
-        .2
-        .values()
-        .filter(|s| s.score > 20)
-        .map(|s| s.nways)
-        .sum();
-    let ways_to_lose_3: usize = maps_p2
-        .2
-        .values()
-        .filter(|s| s.score < 21)
-        .map(|s| s.nways)
-        .sum();
-
-    let ways_to_win_4: usize = maps_p1
-        .3
-        .values()
-        .filter(|s| s.score > 20)
-        .map(|s| s.nways)
-        .sum();
-    let ways_to_lose_4: usize = maps_p2
-        .3
-        .values()
-        .filter(|s| s.score < 21)
-        .map(|s| s.nways)
-        .sum();
-
-    let ways_to_win_5: usize = maps_p1
-        .4
-        .values()
-        .filter(|s| s.score > 20)
-        .map(|s| s.nways)
-        .sum();
-    let ways_to_lose_5: usize = maps_p2
-        .4
-        .values()
-        .filter(|s| s.score < 21)
-        .map(|s| s.nways)
-        .sum();
-
-    let ways_to_win_6: usize = maps_p1
-        .5
-        .values()
-        .filter(|s| s.score > 20)
-        .map(|s| s.nways)
-        .sum();
-    let ways_to_lose_6: usize = maps_p2
-        .5
-        .values()
-        .filter(|s| s.score < 21)
-        .map(|s| s.nways)
-        .sum();
-
-    let ways_to_win_7: usize = maps_p1
-        .6
-        .values()
-        .filter(|s| s.score > 20)
-        .map(|s| s.nways)
-        .sum();
-    let ways_to_lose_7: usize = maps_p2
-        .6
-        .values()
-        .filter(|s| s.score < 21)
-        .map(|s| s.nways)
-        .sum();
-
-    let ways_to_win_8: usize = maps_p1
-        .7
-        .values()
-        .filter(|s| s.score > 20)
-        .map(|s| s.nways)
-        .sum();
-    let ways_to_lose_8: usize = maps_p2
-        .7
-        .values()
-        .filter(|s| s.score < 21)
-        .map(|s| s.nways)
-        .sum();
-
-    let ways_to_win_9: usize = maps_p1
-        .8
-        .values()
-        .filter(|s| s.score > 20)
-        .map(|s| s.nways)
-        .sum();
-    let ways_to_lose_9: usize = maps_p2
-        .8
-        .values()
-        .filter(|s| s.score < 21)
-        .map(|s| s.nways)
-        .sum();
-
-    let ways_to_win_10: usize = maps_p1
-        .9
-        .values()
-        .filter(|s| s.score > 20)
-        .map(|s| s.nways)
-        .sum();
-
-    let mut p1_wins = 0;
-    p1_wins += ways_to_win_3 * ways_to_lose_2;
-    p1_wins += ways_to_win_4 * ways_to_lose_3;
-    p1_wins += ways_to_win_5 * ways_to_lose_4;
-    p1_wins += ways_to_win_6 * ways_to_lose_5;
-    p1_wins += ways_to_win_7 * ways_to_lose_6;
-    p1_wins += ways_to_win_8 * ways_to_lose_7;
-    p1_wins += ways_to_win_9 * ways_to_lose_8;
-    p1_wins += ways_to_win_10 * ways_to_lose_9;
-
-    // Wins start happening in 3 steps, stop happening after 10th step
-    let ways_to_win_3: usize = maps_p2
-        .2
-        .values()
-        .filter(|s| s.score > 20)
-        .map(|s| s.nways)
-        .sum();
-    let ways_to_lose_3: usize = maps_p1
-        .2
-        .values()
-        .filter(|s| s.score < 21)
-        .map(|s| s.nways)
-        .sum();
-
-    let ways_to_win_4: usize = maps_p2
-        .3
-        .values()
-        .filter(|s| s.score > 20)
-        .map(|s| s.nways)
-        .sum();
-    let ways_to_lose_4: usize = maps_p1
-        .3
-        .values()
-        .filter(|s| s.score < 21)
-        .map(|s| s.nways)
-        .sum();
-
-    let ways_to_win_5: usize = maps_p2
-        .4
-        .values()
-        .filter(|s| s.score > 20)
-        .map(|s| s.nways)
-        .sum();
-    let ways_to_lose_5: usize = maps_p1
-        .4
-        .values()
-        .filter(|s| s.score < 21)
-        .map(|s| s.nways)
-        .sum();
-
-    let ways_to_win_6: usize = maps_p2
-        .5
-        .values()
-        .filter(|s| s.score > 20)
-        .map(|s| s.nways)
-        .sum();
-    let ways_to_lose_6: usize = maps_p1
-        .5
-        .values()
-        .filter(|s| s.score < 21)
-        .map(|s| s.nways)
-        .sum();
-
-    let ways_to_win_7: usize = maps_p2
-        .6
-        .values()
-        .filter(|s| s.score > 20)
-        .map(|s| s.nways)
-        .sum();
-    let ways_to_lose_7: usize = maps_p1
-        .6
-        .values()
-        .filter(|s| s.score < 21)
-        .map(|s| s.nways)
-        .sum();
-
-    let ways_to_win_8: usize = maps_p2
-        .7
-        .values()
-        .filter(|s| s.score > 20)
-        .map(|s| s.nways)
-        .sum();
-    let ways_to_lose_8: usize = maps_p1
-        .7
-        .values()
-        .filter(|s| s.score < 21)
-        .map(|s| s.nways)
-        .sum();
-
-    let ways_to_win_9: usize = maps_p2
-        .8
-        .values()
-        .filter(|s| s.score > 20)
-        .map(|s| s.nways)
-        .sum();
-    let ways_to_lose_9: usize = maps_p1
-        .8
-        .values()
-        .filter(|s| s.score < 21)
-        .map(|s| s.nways)
-        .sum();
-
-    let ways_to_win_10: usize = maps_p2
-        .9
-        .values()
-        .filter(|s| s.score > 20)
-        .map(|s| s.nways)
-        .sum();
-    let ways_to_lose_10: usize = maps_p1
-        .9
-        .values()
-        .filter(|s| s.score < 21)
-        .map(|s| s.nways)
-        .sum();
-
-    let mut p2_wins = 0;
-    p2_wins += ways_to_win_3 * ways_to_lose_3;
-    p2_wins += ways_to_win_4 * ways_to_lose_4;
-    p2_wins += ways_to_win_5 * ways_to_lose_5;
-    p2_wins += ways_to_win_6 * ways_to_lose_6;
-    p2_wins += ways_to_win_7 * ways_to_lose_7;
-    p2_wins += ways_to_win_8 * ways_to_lose_8;
-    p2_wins += ways_to_win_9 * ways_to_lose_9;
-    p2_wins += ways_to_win_10 * ways_to_lose_10;
-
-    if p1_wins > p2_wins {
-        p1_wins
-    } else {
-        p2_wins
-    }
+pub fn part2(input: &[Player]) -> u128 {
+    let start = [input[0].position, input[1].position];
+    let wins = DiracGame::PART2.wins(start).unwrap();
+    wins[0].max(wins[1])
 }
 
 #[cfg(test)]
@@ -523,14 +383,114 @@ mod test {
     #[test]
     fn test_part1() {
         let input = read_to_string("input/2021/21.txt").unwrap();
-        let input = load_input(&input);
+        let input = load_input(&input).unwrap();
         assert_eq!(part1(&input), 739785);
     }
 
+    #[test]
+    fn test_bad_input() {
+        let err = load_input("Player 1 starting position: 4").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "2: expected two players, found end of input"
+        );
+        let mut three = read_to_string("input/2021/21.txt").unwrap();
+        three += "Player 3 starting position: 1\n";
+        assert_eq!(
+            load_input(&three).unwrap_err().to_string(),
+            "3:1: expected only two players, found \"Player 3 starting position: 1\""
+        );
+    }
+
     #[test]
     fn test_part2() {
         let input = read_to_string("input/2021/21.txt").unwrap();
-        let input = load_input(&input);
+        let input = load_input(&input).unwrap();
         assert_eq!(part2(&input), 444356092776315);
     }
+
+    #[test]
+    fn test_dirac() {
+        let game = DiracGame::PART2;
+        assert_eq!(game.wins([4, 8]), Some([444356092776315, 341960390180808]));
+        let big = game.big_wins([4, 8]);
+        assert_eq!(big[1], BigUint::from(341960390180808u64));
+
+        // Any first move wins
+        let game = DiracGame { target: 1, ..game };
+        assert_eq!(game.wins([4, 8]), Some([27, 0]));
+
+        // A coin on a six space board, two flips a turn
+        let game = DiracGame {
+            board: 6,
+            target: 4,
+            faces: 2,
+            rolls: 2,
+        };
+        assert_eq!(game.totals(), vec![(2, 1), (3, 2), (4, 1)]);
+        let wins = game.wins([1, 1]).unwrap();
+        let big = game.big_wins([1, 1]);
+        assert_eq!([BigUint::from(wins[0]), BigUint::from(wins[1])], big);
+
+        // Far too many universes for 128 bits
+        let game = DiracGame {
+            board: 3,
+            target: 40,
+            faces: 6,
+            rolls: 3,
+        };
+        assert_eq!(game.wins([1, 2]), None);
+        assert!(game.big_wins([1, 2])[0].bits() > 128);
+    }
+
+    #[test]
+    fn test_dice() {
+        let mut dice = DeterministicDice::with_faces(3);
+        let rolls: Vec<_> = (0..5).map(|_| dice.roll()).collect();
+        assert_eq!(rolls, vec![1, 2, 3, 1, 2]);
+        assert_eq!(dice.outcomes(), vec![(3, 1)]);
+        assert_eq!(DiracDice { faces: 2 }.outcomes(), vec![(1, 1), (2, 1)]);
+
+        // The seed that would zero the state still rolls every face
+        let mut dice = RandomDice::new(0x9e37_79b9_7f4a_7c15, 6);
+        let mut seen: Vec<_> = (0..100).map(|_| dice.roll()).collect();
+        seen.sort();
+        seen.dedup();
+        assert_eq!(seen, vec![1, 2, 3, 4, 5, 6]);
+
+        let players = [Player::new(4), Player::new(8)];
+        let outcome = simulate(&players, &mut DeterministicDice::new(), 10, 3, 1000);
+        assert_eq!(outcome.winner, 0);
+        assert_eq!((outcome.losing_score(), outcome.rolls), (745, 993));
+
+        // A third player on a smaller board, two rolls a turn
+        let three = [Player::new(4), Player::new(8), Player::new(1)];
+        let outcome = simulate(&three, &mut DeterministicDice::new(), 7, 2, 100);
+        assert_eq!(outcome.scores, vec![100, 59, 85]);
+        assert_eq!((outcome.losing_score(), outcome.rolls), (85, 122));
+
+        // Random games should go about the way the exact odds say, even
+        // though player 1 only wins 57% of the universes
+        let exact = DiracGame::PART2.win_chances([4, 8]);
+        assert!((exact[0] + exact[1] - 1.0).abs() < 1e-9);
+        let game = DiracGame::PART2;
+        let estimate = estimate_wins(&players, &mut RandomDice::new(21, 3), &game, 20000);
+        for (estimate, exact) in estimate.iter().zip(exact) {
+            assert!((estimate - exact).abs() < 0.02, "{} vs {}", estimate, exact);
+        }
+
+        // And for other games too
+        let game = DiracGame {
+            board: 6,
+            target: 10,
+            faces: 2,
+            rolls: 2,
+        };
+        let exact = game.win_chances([1, 4]);
+        let players = [Player::new(1), Player::new(4)];
+        let estimate = estimate_wins(&players, &mut RandomDice::new(7, 2), &game, 20000);
+        for (estimate, exact) in estimate.iter().zip(exact) {
+            assert!((estimate - exact).abs() < 0.02, "{} vs {}", estimate, exact);
+        }
+    }
 }
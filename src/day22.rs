@@ -1,3 +1,4 @@
+use crate::error::{next_token, parse_token, ParseError};
 use std::collections::HashSet;
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
@@ -153,7 +154,7 @@ fn remove_cube_from_set(cube: Cube, cubeset: &mut HashSet<Cube>) {
 }
 
 #[derive(Clone, Copy, Debug)]
-struct Instruction {
+pub struct Instruction {
     turn_on: bool,
     cube: Cube,
 }
@@ -172,70 +173,44 @@ impl Instruction {
     }
 }
 
+fn parse_range(idx: usize, line: &str, range: Option<&str>) -> Result<(i64, i64), ParseError> {
+    let range = range.ok_or_else(|| ParseError::missing(idx, line, "a range"))?;
+    let range = range.split(',').next().unwrap_or(range);
+    let mut iter = range.split("..");
+    let min = parse_token(idx, line, iter.next(), "a range start")?;
+    let max: i64 = parse_token(idx, line, iter.next(), "'..'")?;
+    if max < min {
+        return Err(ParseError::invalid(idx, line, range, "an increasing range"));
+    }
+    Ok((min, max + 1))
+}
+
 #[aoc_generator(day22)]
-fn load_input(input: &str) -> Vec<Instruction> {
+pub fn load_input(input: &str) -> Result<Vec<Instruction>, ParseError> {
     let mut output = vec![];
-    for line in input.lines() {
+    for (idx, line) in input.lines().enumerate() {
         let mut liter = line.split(' ');
 
-        let turn_on;
-        if liter.next().unwrap() == "on" {
-            turn_on = true;
-        } else {
-            turn_on = false;
-        }
+        let state = next_token(idx, line, &mut liter, "on or off")?;
+        let turn_on = match state {
+            "on" => true,
+            "off" => false,
+            _ => return Err(ParseError::invalid(idx, line, state, "on or off")),
+        };
 
         let mut liter = line.split('=');
         liter.next();
-        let xstring = liter.next().unwrap();
-        let ystring = liter.next().unwrap();
-        let zstring = liter.next().unwrap();
-
-        let mut xiter = xstring.split("..");
-        let xmin = xiter.next().unwrap().parse::<i64>().unwrap();
-        let xmax = xiter
-            .next()
-            .unwrap()
-            .split(',')
-            .next()
-            .unwrap()
-            .parse::<i64>()
-            .unwrap();
-
-        let mut yiter = ystring.split("..");
-        let ymin = yiter.next().unwrap().parse::<i64>().unwrap();
-        let ymax = yiter
-            .next()
-            .unwrap()
-            .split(',')
-            .next()
-            .unwrap()
-            .parse::<i64>()
-            .unwrap();
-
-        let mut ziter = zstring.split("..");
-        let zmin = ziter.next().unwrap().parse::<i64>().unwrap();
-        let zmax = ziter
-            .next()
-            .unwrap()
-            .split(',')
-            .next()
-            .unwrap()
-            .parse::<i64>()
-            .unwrap();
-
-        output.push(Instruction::new(
-            turn_on,
-            (xmin, xmax + 1),
-            (ymin, ymax + 1),
-            (zmin, zmax + 1),
-        ));
+        let xvalues = parse_range(idx, line, liter.next())?;
+        let yvalues = parse_range(idx, line, liter.next())?;
+        let zvalues = parse_range(idx, line, liter.next())?;
+
+        output.push(Instruction::new(turn_on, xvalues, yvalues, zvalues));
     }
-    output
+    Ok(output)
 }
 
 #[aoc(day22, part1)]
-fn part1(input: &[Instruction]) -> usize {
+pub fn part1(input: &[Instruction]) -> usize {
     let mut on_set: HashSet<Cube> = HashSet::new();
 
     for inst in input.iter().take(20) {
@@ -272,7 +247,7 @@ fn intersect_1d(range1: (i64, i64), range2: (i64, i64)) -> Option<(i64, i64)> {
 }
 
 #[aoc(day22, part2)]
-fn part2(input: &[Instruction]) -> usize {
+pub fn part2(input: &[Instruction]) -> usize {
     let mut on_set: HashSet<Cube> = HashSet::new();
 
     for inst in input {
@@ -300,14 +275,14 @@ mod test {
     #[test]
     fn test_part1() {
         let input = read_to_string("input/2021/22a.txt").unwrap();
-        let input = load_input(&input);
+        let input = load_input(&input).unwrap();
         assert_eq!(part1(&input), 590784);
     }
 
     #[test]
     fn test_part2() {
         let input = read_to_string("input/2021/22b.txt").unwrap();
-        let input = load_input(&input);
+        let input = load_input(&input).unwrap();
         assert_eq!(part2(&input), 2758514936282235);
     }
 }
@@ -1,667 +1,676 @@
-use std::collections::HashSet;
+use crate::error::ParseError;
+use crate::search::{astar, Route};
+use std::error::Error;
+use std::fmt;
+use termion::{color, style};
+
+/// An empty cell in `State`, amphipods are stored as their kind plus one.
+const EMPTY: u8 = 0;
+
+/// The rows folded away in the part 1 diagram, just below the top row, drawn
+/// for a diagram starting at the left margin.
+pub const PART2_ROWS: &str = "  #D#C#B#A#\n  #D#B#A#C#";
+
+/// The fixed shape of a burrow along with where everybody starts.
+///
+/// ```text
+/// #############
+/// #...........#   <- hallway, cells 0..hallway
+/// ###B#C#B#D###   <- depth 0 of each room
+///   #A#D#C#A#     <- depth 1
+///   #########
+/// ```
+///
+/// Room `k` belongs to amphipod kind `k` ('A' + k), which costs `10^k` energy
+/// per step.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Burrow {
+    /// Columns before the top wall.
+    left: usize,
+    hallway: usize,
+    rooms: Vec<usize>,
+    depth: usize,
+    start: State,
+}
 
-#[aoc_generator(day23)]
-fn load_input(input: &str) -> Vec<char> {
-    let mut output = vec![];
-    for line in input.lines().skip(2) {
-        for c in line.chars().filter(|c| c.is_alphabetic()) {
-            output.push(c);
+/// The hallway cells followed by every room from top to bottom.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct State(Vec<u8>);
+
+/// Somewhere an amphipod can stand.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Spot {
+    /// Cell of the hallway, counting from the left.
+    Hallway(usize),
+    /// Room and how far down it, 0 being the top.
+    Room(usize, usize),
+}
+
+impl fmt::Display for Spot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Spot::Hallway(x) => write!(f, "hallway {}", x),
+            Spot::Room(r, d) => write!(f, "room {} depth {}", (b'A' + *r as u8) as char, d),
         }
     }
-    output
 }
 
-struct Game {
-    states_to_play: HashSet<State>,
-    best_score: usize,
+/// One amphipod walking from one spot to another without stopping.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Move {
+    pub amphipod: char,
+    pub from: Spot,
+    pub to: Spot,
+    pub cost: usize,
 }
 
-impl Game {
-    fn from_letters(letters: &[char]) -> Game {
-        let mut states_to_play = HashSet::new();
-        states_to_play.insert(State::from_letters(letters));
-        Game {
-            states_to_play,
-            best_score: usize::MAX,
-        }
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} from {} to {} ({} energy)",
+            self.amphipod, self.from, self.to, self.cost
+        )
     }
+}
 
-    fn step(&mut self) {
-        let next_states: Vec<Vec<State>> = self
-            .states_to_play
-            .iter()
-            .map(|s| s.valid_moves())
-            .collect();
-        let mut next_game_states = HashSet::new();
+/// Why `Burrow::unfold` couldn't add the rows.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum UnfoldError {
+    /// Asked to unfold `at` rows down into rooms only `depth` deep.
+    TooDeep { at: usize, depth: usize },
+    /// The rows themselves are no good.
+    Rows(ParseError),
+}
 
-        for svec in next_states {
-            for s in svec {
-                if s.finished_state() && s.cost < self.best_score {
-                    self.best_score = s.cost;
-                }
-                if s.cost < self.best_score {
-                    next_game_states.insert(s);
-                }
-            }
+impl fmt::Display for UnfoldError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            UnfoldError::TooDeep { at, depth } => write!(
+                f,
+                "can't unfold {} rows down, the rooms are only {} deep",
+                at, depth
+            ),
+            UnfoldError::Rows(e) => write!(f, "unfolded rows: {}", e),
         }
-        self.states_to_play = next_game_states;
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
-struct State {
-    hallway: [char; 7],
-    rooms: [[char; 2]; 4],
-    cost: usize,
-}
+impl Error for UnfoldError {}
 
-fn letter_value(letter: char) -> usize {
-    match letter {
-        'A' => 1,
-        'B' => 10,
-        'C' => 100,
-        'D' => 1000,
-        _ => panic!("Invalid letter"),
+impl From<ParseError> for UnfoldError {
+    fn from(e: ParseError) -> UnfoldError {
+        UnfoldError::Rows(e)
     }
 }
 
-impl State {
-    fn from_letters(letters: &[char]) -> State {
-        let mut rooms = [['.', '.'], ['.', '.'], ['.', '.'], ['.', '.']];
-        rooms[0][0] = letters[0];
-        rooms[0][1] = letters[4];
-        rooms[1][0] = letters[1];
-        rooms[1][1] = letters[5];
-        rooms[2][0] = letters[2];
-        rooms[2][1] = letters[6];
-        rooms[3][0] = letters[3];
-        rooms[3][1] = letters[7];
-        State {
-            hallway: ['.'; 7],
-            rooms,
-            cost: 0,
+/// Why a part couldn't be answered.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SolveError {
+    Unfold(UnfoldError),
+    /// The amphipods block each other in, so they can never all get home.
+    NoSolution,
+}
+
+impl fmt::Display for SolveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SolveError::Unfold(e) => write!(f, "{}", e),
+            SolveError::NoSolution => write!(f, "no way to sort the burrow"),
         }
     }
+}
 
-    fn finished_state(&self) -> bool {
-        for ridx in 0..4 {
-            if !self.room_in_phase_2(ridx) {
-                return false;
-            }
-        }
+impl Error for SolveError {}
 
-        if self.hallway.iter().filter(|&&h| h != '.').count() > 0 {
-            return false;
-        }
-        true
+impl From<UnfoldError> for SolveError {
+    fn from(e: UnfoldError) -> SolveError {
+        SolveError::Unfold(e)
     }
+}
 
-    // #############
-    // #01.2.3.4.56#
-    // ###0#1#2#3###
-    //   #0#1#2#3#
-    fn moves_left(&self, ridx: usize) -> Vec<State> {
-        let mut output = vec![];
-        // Bail immediately if nothing to move
-        if self.rooms[ridx][0] == '.' && self.rooms[ridx][1] == '.' {
-            return output;
-        }
-        // Bail if room is in phase 2
-        if self.room_in_phase_2(ridx) {
-            return output;
+impl Burrow {
+    /// Read a burrow diagram. The hallway is the line under the top wall,
+    /// every column holding amphipods on the lines below it is a room, and
+    /// there has to be one room per kind of amphipod with exactly enough of
+    /// each kind to fill its room.
+    pub fn parse(input: &str) -> Result<Burrow, ParseError> {
+        let lines: Vec<&str> = input.lines().collect();
+        let top = lines
+            .first()
+            .ok_or_else(|| ParseError::eof(0, "a burrow diagram"))?
+            .trim_end();
+        let left = top
+            .find('#')
+            .ok_or_else(|| ParseError::missing(0, top, "the top wall"))?;
+        let width = top.len() - left;
+        if let Some((col, c)) = top.chars().enumerate().skip(left).find(|&(_, c)| c != '#') {
+            return Err(ParseError::invalid_char(0, col, c, "a wall"));
         }
-        let start = 1 + ridx;
-        let mut cntr = 0;
-
-        let mut hidx = start - cntr;
-        let mut hallway_moves = 1;
-        while self.hallway[hidx] == '.' {
-            let mut temp = *self;
-            let mut didx = 0;
-            while temp.rooms[ridx][didx] == '.' {
-                didx += 1;
-            }
-            let letter = temp.rooms[ridx][didx];
-            temp.rooms[ridx][didx] = '.';
-            temp.hallway[hidx] = letter;
-
-            // Cost to move to hallway
-            let mut cost = 1 + didx;
-            // Cost to move along hallway
-            cost += hallway_moves;
-            temp.cost += cost * letter_value(letter);
 
-            output.push(temp);
+        let hall_line = lines
+            .get(1)
+            .ok_or_else(|| ParseError::eof(1, "a hallway"))?;
+        let hallway = width.saturating_sub(2);
+        let mut cells = vec![];
+        let mut found = vec![];
+        for (col, c) in hall_line.trim_end().chars().enumerate().skip(left) {
+            let inside = col > left && col <= left + hallway;
+            if inside && c == '#' {
+                return Err(ParseError::invalid_char(
+                    1,
+                    col,
+                    c,
+                    "a hallway as wide as the top wall",
+                ));
+            } else if inside {
+                cells.push(cell(1, col, c)?);
+                if c != '.' {
+                    found.push((1, col, c));
+                }
+            } else if c != '#' || col > left + hallway + 1 {
+                return Err(ParseError::invalid_char(1, col, c, "a wall"));
+            }
+        }
+        if hall_line.trim_end().len() != left + width || hallway == 0 {
+            return Err(ParseError::missing(
+                1,
+                hall_line,
+                "a hallway as wide as the top wall",
+            ));
+        }
 
-            // We're done
-            if hidx == 0 {
+        let mut rooms: Vec<usize> = vec![];
+        let mut room_cells: Vec<Vec<u8>> = vec![];
+        let mut bottom = None;
+        for (idx, line) in lines.iter().enumerate().skip(2) {
+            let row = room_row(idx, line)?;
+            if row.is_empty() {
+                bottom = Some(idx);
                 break;
-            } else if hidx == 1 {
-                hallway_moves += 1;
-            } else {
-                hallway_moves += 2;
             }
-            cntr += 1;
-            hidx = start - cntr;
+            if rooms.is_empty() {
+                rooms = row
+                    .iter()
+                    .map(|&(col, _)| col.wrapping_sub(left + 1))
+                    .collect();
+                if rooms.iter().any(|&x| x >= hallway) {
+                    return Err(ParseError::invalid(
+                        idx,
+                        line,
+                        line,
+                        "rooms under the hallway",
+                    ));
+                }
+                room_cells = vec![vec![]; rooms.len()];
+            }
+            check_aligned(idx, line, &row, &rooms, left + 1)?;
+            for (room, &(col, c)) in room_cells.iter_mut().zip(&row) {
+                room.push(cell(idx, col, c)?);
+                found.push((idx, col, c));
+            }
         }
-        output
-    }
-
-    fn moves_right(&self, ridx: usize) -> Vec<State> {
-        let mut output = vec![];
-        // Bail immediately if nothing to move
-        if self.rooms[ridx].iter().filter(|&&x| x != '.').count() == 0 {
-            return output;
+        let bottom = bottom.ok_or_else(|| ParseError::eof(lines.len(), "the bottom wall"))?;
+        if rooms.is_empty() {
+            return Err(ParseError::invalid(
+                bottom,
+                lines[bottom],
+                lines[bottom],
+                "a row of rooms",
+            ));
         }
-        // Bail if room is in phase 2
-        if self.room_in_phase_2(ridx) {
-            return output;
+        if let Some((idx, line)) = lines
+            .iter()
+            .enumerate()
+            .skip(bottom + 1)
+            .find(|(_, l)| !l.trim().is_empty())
+        {
+            return Err(ParseError::invalid(
+                idx,
+                line,
+                line.trim(),
+                "nothing after the bottom wall",
+            ));
         }
-        let start = 2 + ridx;
-        let mut cntr = 0;
-
-        let mut hidx = start + cntr;
-        let mut hallway_moves = 1;
-        while self.hallway[hidx] == '.' {
-            let mut temp = *self;
-            let mut didx = 0;
-            while temp.rooms[ridx][didx] == '.' {
-                didx += 1;
+        for &x in &rooms {
+            if cells[x] != EMPTY {
+                let col = left + 1 + x;
+                let c = hall_line.chars().nth(col).unwrap();
+                return Err(ParseError::invalid_char(1, col, c, "nobody outside a room"));
             }
-            let letter = temp.rooms[ridx][didx];
-            temp.rooms[ridx][didx] = '.';
-            temp.hallway[hidx] = letter;
-
-            // Cost to move to hallway
-            let mut cost = 1 + didx;
-            // Cost to move along hallway
-            cost += hallway_moves;
-            temp.cost += cost * letter_value(letter);
-
-            output.push(temp);
+        }
 
-            // We're done
-            if hidx == 6 {
-                break;
-            } else if hidx == 5 {
-                hallway_moves += 1;
-            } else {
-                hallway_moves += 2;
-            }
-            cntr += 1;
-            hidx = start + cntr;
+        let depth = room_cells[0].len();
+        check_counts(&found, rooms.len(), depth)?;
+        for room in room_cells {
+            cells.extend(room);
         }
-        output
+        Ok(Burrow {
+            left,
+            hallway,
+            rooms,
+            depth,
+            start: State(cells),
+        })
     }
 
-    fn room_in_phase_2(&self, ridx: usize) -> bool {
-        let expected_letter = match ridx {
-            0 => 'A',
-            1 => 'B',
-            2 => 'C',
-            3 => 'D',
-            _ => panic!("Invalid room index"),
-        };
-        self.rooms[ridx]
-            .iter()
-            .filter(|&&r| r != expected_letter && r != '.')
-            .count()
-            == 0
+    pub fn depth(&self) -> usize {
+        self.depth
     }
 
-    fn hallway_to_room_moves(&self, ridx: usize) -> Vec<State> {
-        let mut output = vec![];
+    pub fn rooms(&self) -> usize {
+        self.rooms.len()
+    }
 
-        // First check to see if this room is even ready yet
-        if !self.room_in_phase_2(ridx) {
-            return output;
+    /// Push more amphipods into the rooms, starting `at` rows down from the
+    /// top. `rows` are drawn like the room rows of the diagram, in the same
+    /// columns, and hold a full set of amphipods between them.
+    pub fn unfold(&self, at: usize, rows: &str) -> Result<Burrow, UnfoldError> {
+        if at > self.depth {
+            return Err(UnfoldError::TooDeep {
+                at,
+                depth: self.depth,
+            });
         }
-
-        // Room only admits certain letters if in phase 2
-        let expected_letter = match ridx {
-            0 => 'A',
-            1 => 'B',
-            2 => 'C',
-            3 => 'D',
-            _ => panic!("Invalid room index"),
-        };
-
-        // Room is ready if we get here
-        for hidx in 0..7 {
-            // Make sure there's an appropriate letter here
-            if self.hallway[hidx] != expected_letter {
-                continue;
-            }
-
-            let mut valid = true;
-            if ridx + 2 > hidx {
-                // Moving to the right
-                for i in hidx + 1..ridx + 2 {
-                    if self.hallway[i] != '.' {
-                        valid = false;
-                    }
-                }
-            } else {
-                // Moving to the left
-                for i in ridx + 2..hidx {
-                    if self.hallway[i] != '.' {
-                        valid = false;
-                    }
-                }
-            }
-
-            if valid {
-                // If we have a valid letter, and the path to the room is
-                // unblocked, we can move
-                let mut temp = *self;
-                let letter = temp.hallway[hidx];
-                temp.hallway[hidx] = '.';
-                let mut didx = temp.rooms[ridx].len() - 1;
-                while temp.rooms[ridx][didx] != '.' && didx > 0 {
-                    didx -= 1;
-                }
-                temp.rooms[ridx][didx] = letter;
-                // Cost to move to room
-                let mut cost = 2 + didx;
-                // Cost to move along hallway
-                let mut moves = 0;
-                if ridx + 2 > hidx {
-                    // Moving to the right
-                    if hidx == 0 {
-                        moves += 1;
-                        moves += 2 * (ridx - hidx);
-                    } else {
-                        moves = 2 * (ridx - (hidx - 1));
-                    }
-                } else {
-                    // Moving to the left
-                    if hidx == 6 {
-                        moves += 1;
-                        moves += 2 * (hidx - 3 - ridx);
-                    } else {
-                        moves = 2 * (hidx - 2 - ridx);
-                    }
-                }
-                cost += moves;
-                temp.cost += cost * letter_value(letter);
-
-                output.push(temp);
+        let mut extra = vec![];
+        let mut found = vec![];
+        for (idx, line) in rows.lines().enumerate() {
+            let row = room_row(idx, line)?;
+            check_aligned(idx, line, &row, &self.rooms, self.left + 1)?;
+            for &(col, c) in &row {
+                found.push((idx, col, c));
             }
+            extra.push(row);
         }
-        output
+        check_counts(&found, self.rooms.len(), extra.len())?;
+
+        let mut cells = self.start.0[..self.hallway].to_vec();
+        for r in 0..self.rooms.len() {
+            let room = &self.start.0[self.slot(r, 0)..self.slot(r, self.depth)];
+            cells.extend(&room[..at]);
+            cells.extend(extra.iter().map(|row| cell(0, 0, row[r].1).unwrap()));
+            cells.extend(&room[at..]);
+        }
+        Ok(Burrow {
+            left: self.left,
+            hallway: self.hallway,
+            rooms: self.rooms.clone(),
+            depth: self.depth + extra.len(),
+            start: State(cells),
+        })
     }
 
-    fn valid_moves(&self) -> Vec<State> {
-        let mut output = vec![];
-        for ridx in 0..4 {
-            // Now enumerate hallway -> room moves
-            // NOTE: IF any of these are possible, they are always the best
-            // move to make, so we won't even bother reporting the others
-            let moves = self.hallway_to_room_moves(ridx);
-            if !moves.is_empty() {
-                for m in moves {
-                    output.push(m);
-                }
-                return output;
-            }
-            // First enumerate room -> hallway moves to the left
-            let moves = self.moves_left(ridx);
-            for m in moves {
-                output.push(m);
-            }
-            // Now enumerate room -> hallway moves to the right
-            let moves = self.moves_right(ridx);
-            for m in moves {
-                output.push(m);
-            }
-        }
-        output
+    /// Indent `rows`, drawn for a diagram starting at the left margin, to line
+    /// up with this one.
+    pub fn indent(&self, rows: &str) -> String {
+        let margin = " ".repeat(self.left);
+        rows.lines()
+            .map(|line| format!("{}{}", margin, line))
+            .collect::<Vec<_>>()
+            .join("\n")
     }
-}
 
-#[aoc(day23, part1)]
-fn part1(input: &[char]) -> usize {
-    let mut game = Game::from_letters(input);
+    /// Least energy needed to get every amphipod home, if they can get there
+    /// at all.
+    pub fn solve(&self) -> Option<usize> {
+        self.route().map(|route| route.cost)
+    }
 
-    // Play all possible games
-    while !game.states_to_play.is_empty() {
-        game.step();
+    /// The cheapest way to get every amphipod home, one move at a time.
+    pub fn best_moves(&self) -> Option<Vec<Move>> {
+        let moves = self
+            .route()?
+            .states
+            .windows(2)
+            .map(|pair| {
+                let (from, to) = changed(&pair[0], &pair[1]);
+                let kind = pair[1].0[to];
+                let (from, to) = (self.spot(from), self.spot(to));
+                Move {
+                    amphipod: (b'A' + kind - 1) as char,
+                    from,
+                    to,
+                    cost: self.distance(from, to) * energy(kind),
+                }
+            })
+            .collect();
+        Some(moves)
     }
-    game.best_score
-}
 
-struct Game2 {
-    states_to_play: HashSet<State2>,
-    best_score: usize,
-}
+    /// The diagram as it was parsed.
+    pub fn render(&self) -> String {
+        self.draw(&self.start, None)
+    }
 
-impl Game2 {
-    fn from_letters(letters: &[char]) -> Game2 {
-        let mut states_to_play = HashSet::new();
-        states_to_play.insert(State2::from_letters(letters));
-        Game2 {
-            states_to_play,
-            best_score: usize::MAX,
+    /// The diagram before any move and again after each of `moves`, with
+    /// whoever just moved picked out in colour.
+    pub fn frames(&self, moves: &[Move]) -> Vec<String> {
+        let mut state = self.start.clone();
+        let mut frames = vec![self.draw(&state, None)];
+        for m in moves {
+            let (from, to) = (self.index(m.from), self.index(m.to));
+            state.0[to] = state.0[from];
+            state.0[from] = EMPTY;
+            frames.push(self.draw(&state, Some(to)));
         }
+        frames
     }
 
-    fn step(&mut self) {
-        let next_states: Vec<Vec<State2>> = self
-            .states_to_play
-            .iter()
-            .map(|s| s.valid_moves())
-            .collect();
-        let mut next_game_states = HashSet::new();
+    fn draw(&self, s: &State, moved: Option<usize>) -> String {
+        let width = self.hallway + 2;
+        let first = self.rooms[0];
+        let last = self.rooms[self.rooms.len() - 1] + 2;
+        let name = |idx: usize| {
+            let c = match s.0[idx] {
+                EMPTY => '.',
+                kind => (b'A' + kind - 1) as char,
+            };
+            if moved == Some(idx) {
+                format!(
+                    "{}{}{}{}{}",
+                    style::Bold,
+                    color::Fg(color::Red),
+                    c,
+                    color::Fg(color::Reset),
+                    style::Reset
+                )
+            } else {
+                c.to_string()
+            }
+        };
 
-        for svec in next_states {
-            for s in svec {
-                if s.finished_state() && s.cost < self.best_score {
-                    self.best_score = s.cost;
-                }
-                if s.cost < self.best_score {
-                    next_game_states.insert(s);
+        let mut out = "#".repeat(width);
+        out += "\n#";
+        for x in 0..self.hallway {
+            out += &name(x);
+        }
+        out += "#";
+        for d in 0..=self.depth {
+            out.push('\n');
+            for col in 0..width {
+                let room = col
+                    .checked_sub(1)
+                    .and_then(|x| self.rooms.iter().position(|&r| r == x));
+                match room {
+                    Some(r) if d < self.depth => out += &name(self.slot(r, d)),
+                    _ if d == 0 || (first..=last).contains(&col) => out.push('#'),
+                    _ if col < first => out.push(' '),
+                    _ => break,
                 }
             }
         }
-        self.states_to_play = next_game_states;
+        out
     }
-}
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
-struct State2 {
-    hallway: [char; 7],
-    rooms: [[char; 4]; 4],
-    cost: usize,
-}
+    fn route(&self) -> Option<Route<State, usize>> {
+        astar(
+            self.start.clone(),
+            |s| self.moves(s),
+            |s| self.heuristic(s),
+            |s| self.is_sorted(s),
+        )
+    }
 
-impl State2 {
-    fn from_letters(letters: &[char]) -> State2 {
-        let mut rooms = [
-            ['.', '.', '.', '.'],
-            ['.', '.', '.', '.'],
-            ['.', '.', '.', '.'],
-            ['.', '.', '.', '.'],
-        ];
-
-        rooms[0][0] = letters[0];
-        rooms[0][1] = letters[4];
-        rooms[0][2] = letters[8];
-        rooms[0][3] = letters[12];
-
-        rooms[1][0] = letters[1];
-        rooms[1][1] = letters[5];
-        rooms[1][2] = letters[9];
-        rooms[1][3] = letters[13];
-
-        rooms[2][0] = letters[2];
-        rooms[2][1] = letters[6];
-        rooms[2][2] = letters[10];
-        rooms[2][3] = letters[14];
-
-        rooms[3][0] = letters[3];
-        rooms[3][1] = letters[7];
-        rooms[3][2] = letters[11];
-        rooms[3][3] = letters[15];
-
-        State2 {
-            hallway: ['.'; 7],
-            rooms,
-            cost: 0,
-        }
+    fn slot(&self, room: usize, depth: usize) -> usize {
+        self.hallway + room * self.depth + depth
     }
 
-    fn finished_state(&self) -> bool {
-        for ridx in 0..4 {
-            if !self.room_in_phase_2(ridx) {
-                return false;
-            }
+    fn spot(&self, idx: usize) -> Spot {
+        if idx < self.hallway {
+            Spot::Hallway(idx)
+        } else {
+            let idx = idx - self.hallway;
+            Spot::Room(idx / self.depth, idx % self.depth)
         }
+    }
 
-        if self.hallway.iter().filter(|&&h| h != '.').count() > 0 {
-            return false;
+    fn index(&self, spot: Spot) -> usize {
+        match spot {
+            Spot::Hallway(x) => x,
+            Spot::Room(r, d) => self.slot(r, d),
         }
-        true
     }
 
-    // #############
-    // #01.2.3.4.56#
-    // ###0#1#2#3###
-    //   #0#1#2#3#
-    //   #0#1#2#3#
-    //   #0#1#2#3#
-    fn moves_left(&self, ridx: usize) -> Vec<State2> {
-        let mut output = vec![];
-        // Bail immediately if nothing to move
-        if self.rooms[ridx].iter().filter(|&&x| x != '.').count() == 0 {
-            return output;
-        }
-        // Bail if room is in phase 2
-        if self.room_in_phase_2(ridx) {
-            return output;
-        }
-        let start = 1 + ridx;
-        let mut cntr = 0;
-
-        let mut hidx = start - cntr;
-        let mut hallway_moves = 1;
-        while self.hallway[hidx] == '.' {
-            let mut temp = *self;
-            let mut didx = 0;
-            while temp.rooms[ridx][didx] == '.' {
-                didx += 1;
+    /// Steps between two spots, going through the hallway.
+    fn distance(&self, from: Spot, to: Spot) -> usize {
+        match (from, to) {
+            (Spot::Hallway(a), Spot::Hallway(b)) => a.abs_diff(b),
+            (Spot::Hallway(x), Spot::Room(r, d)) | (Spot::Room(r, d), Spot::Hallway(x)) => {
+                x.abs_diff(self.rooms[r]) + d + 1
             }
-            let letter = temp.rooms[ridx][didx];
-            temp.rooms[ridx][didx] = '.';
-            temp.hallway[hidx] = letter;
-
-            // Cost to move to hallway
-            let mut cost = 1 + didx;
-            // Cost to move along hallway
-            cost += hallway_moves;
-            temp.cost += cost * letter_value(letter);
-
-            output.push(temp);
-
-            // We're done
-            if hidx == 0 {
-                break;
-            } else if hidx == 1 {
-                hallway_moves += 1;
-            } else {
-                hallway_moves += 2;
+            (Spot::Room(a, i), Spot::Room(b, j)) => {
+                self.rooms[a].abs_diff(self.rooms[b]).max(2) + i + j + 2
             }
-            cntr += 1;
-            hidx = start - cntr;
         }
-        output
     }
 
-    fn moves_right(&self, ridx: usize) -> Vec<State2> {
-        let mut output = vec![];
-        // Bail immediately if nothing to move
-        if self.rooms[ridx].iter().filter(|&&x| x != '.').count() == 0 {
-            return output;
-        }
-        // Bail if room is in phase 2
-        if self.room_in_phase_2(ridx) {
-            return output;
-        }
-        let start = 2 + ridx;
-        let mut cntr = 0;
-
-        let mut hidx = start + cntr;
-        let mut hallway_moves = 1;
-        while self.hallway[hidx] == '.' {
-            let mut temp = *self;
-            let mut didx = 0;
-            while temp.rooms[ridx][didx] == '.' {
-                didx += 1;
-            }
-            let letter = temp.rooms[ridx][didx];
-            temp.rooms[ridx][didx] = '.';
-            temp.hallway[hidx] = letter;
-
-            // Cost to move to hallway
-            let mut cost = 1 + didx;
-            // Cost to move along hallway
-            cost += hallway_moves;
-            temp.cost += cost * letter_value(letter);
-
-            output.push(temp);
+    /// Whether `room` only holds amphipods that belong there.
+    fn is_settled(&self, s: &State, room: usize) -> bool {
+        (0..self.depth).all(|d| {
+            let kind = s.0[self.slot(room, d)];
+            kind == EMPTY || kind as usize == room + 1
+        })
+    }
 
-            // We're done
-            if hidx == 6 {
-                break;
-            } else if hidx == 5 {
-                hallway_moves += 1;
-            } else {
-                hallway_moves += 2;
-            }
-            cntr += 1;
-            hidx = start + cntr;
-        }
-        output
+    fn is_sorted(&self, s: &State) -> bool {
+        (0..self.rooms.len())
+            .all(|r| (0..self.depth).all(|d| s.0[self.slot(r, d)] as usize == r + 1))
     }
 
-    fn room_in_phase_2(&self, ridx: usize) -> bool {
-        let expected_letter = match ridx {
-            0 => 'A',
-            1 => 'B',
-            2 => 'C',
-            3 => 'D',
-            _ => panic!("Invalid room index"),
+    fn is_clear(&self, s: &State, from: usize, to: usize) -> bool {
+        let (lo, hi) = if from < to {
+            (from + 1, to)
+        } else {
+            (to, from - 1)
         };
-        self.rooms[ridx]
-            .iter()
-            .filter(|&&r| r != expected_letter && r != '.')
-            .count()
-            == 0
+        s.0[lo..=hi].iter().all(|&c| c == EMPTY)
     }
 
-    fn hallway_to_room_moves(&self, ridx: usize) -> Vec<State2> {
-        let mut output = vec![];
-
-        // First check to see if this room is even ready yet
-        if !self.room_in_phase_2(ridx) {
-            return output;
+    fn moves(&self, s: &State) -> Vec<(State, usize)> {
+        // Going straight home is never a mistake, so when somebody can do that
+        // it's the only move worth looking at
+        for x in 0..self.hallway {
+            let kind = s.0[x];
+            if kind == EMPTY {
+                continue;
+            }
+            let room = kind as usize - 1;
+            let door = self.rooms[room];
+            if !self.is_settled(s, room) || !self.is_clear(s, x, door) {
+                continue;
+            }
+            let depth = (0..self.depth)
+                .rev()
+                .find(|&d| s.0[self.slot(room, d)] == EMPTY)
+                .unwrap();
+            let mut next = s.clone();
+            next.0[x] = EMPTY;
+            next.0[self.slot(room, depth)] = kind;
+            let steps = x.abs_diff(door) + depth + 1;
+            return vec![(next, steps * energy(kind))];
         }
 
-        // Room only admits certain letters if in phase 2
-        let expected_letter = match ridx {
-            0 => 'A',
-            1 => 'B',
-            2 => 'C',
-            3 => 'D',
-            _ => panic!("Invalid room index"),
-        };
-
-        // Room is ready if we get here
-        for hidx in 0..7 {
-            // Make sure there's an appropriate letter here
-            if self.hallway[hidx] != expected_letter {
+        let mut output = vec![];
+        for (room, &door) in self.rooms.iter().enumerate() {
+            if self.is_settled(s, room) {
                 continue;
             }
-
-            let mut valid = true;
-            if ridx + 2 > hidx {
-                // Moving to the right
-                for i in hidx + 1..ridx + 2 {
-                    if self.hallway[i] != '.' {
-                        valid = false;
-                    }
-                }
-            } else {
-                // Moving to the left
-                for i in ridx + 2..hidx {
-                    if self.hallway[i] != '.' {
-                        valid = false;
-                    }
+            let depth = (0..self.depth)
+                .find(|&d| s.0[self.slot(room, d)] != EMPTY)
+                .unwrap();
+            let kind = s.0[self.slot(room, depth)];
+            let left = (0..door).rev().take_while(|&x| s.0[x] == EMPTY);
+            let right = (door + 1..self.hallway).take_while(|&x| s.0[x] == EMPTY);
+            for x in left.chain(right) {
+                // Nobody may stop right outside a room
+                if self.rooms.contains(&x) {
+                    continue;
                 }
+                let mut next = s.clone();
+                next.0[self.slot(room, depth)] = EMPTY;
+                next.0[x] = kind;
+                let steps = depth + 1 + x.abs_diff(door);
+                output.push((next, steps * energy(kind)));
             }
+        }
+        output
+    }
 
-            if valid {
-                // If we have a valid letter, and the path to the room is
-                // unblocked, we can move
-                let mut temp = *self;
-                let letter = temp.hallway[hidx];
-                temp.hallway[hidx] = '.';
-                let mut didx = temp.rooms[ridx].len() - 1;
-                while temp.rooms[ridx][didx] != '.' && didx > 0 {
-                    didx -= 1;
+    /// Energy to walk everybody who isn't home yet straight to the top of
+    /// their room ignoring each other, plus what it takes to fill the rooms
+    /// from the bottom. Nobody can do it cheaper so A* stays exact.
+    fn heuristic(&self, s: &State) -> usize {
+        let mut total = 0;
+        let mut incoming = vec![0; self.rooms.len()];
+        for x in 0..self.hallway {
+            let kind = s.0[x];
+            if kind != EMPTY {
+                let room = kind as usize - 1;
+                total += (x.abs_diff(self.rooms[room]) + 1) * energy(kind);
+                incoming[room] += 1;
+            }
+        }
+        for (r, &door) in self.rooms.iter().enumerate() {
+            // Everybody above a stranger has to leave, even if they're home
+            let mut blocked = false;
+            for d in (0..self.depth).rev() {
+                let kind = s.0[self.slot(r, d)];
+                if kind == EMPTY {
+                    continue;
                 }
-                temp.rooms[ridx][didx] = letter;
-                // Cost to move to room
-                let mut cost = 2 + didx;
-                // Cost to move along hallway
-                let mut moves = 0;
-                if ridx + 2 > hidx {
-                    // Moving to the right
-                    if hidx == 0 {
-                        moves += 1;
-                        moves += 2 * (ridx - hidx);
-                    } else {
-                        moves = 2 * (ridx - (hidx - 1));
-                    }
-                } else {
-                    // Moving to the left
-                    if hidx == 6 {
-                        moves += 1;
-                        moves += 2 * (hidx - 3 - ridx);
-                    } else {
-                        moves = 2 * (hidx - 2 - ridx);
-                    }
+                let room = kind as usize - 1;
+                blocked |= room != r;
+                if blocked {
+                    let across = door.abs_diff(self.rooms[room]).max(2);
+                    total += (d + 1 + across + 1) * energy(kind);
+                    incoming[room] += 1;
                 }
-                cost += moves;
-                temp.cost += cost * letter_value(letter);
-
-                output.push(temp);
             }
         }
-        output
+        for (r, &n) in incoming.iter().enumerate() {
+            // Only the first one in stops at the top, the rest go deeper
+            let extra: usize = (0..n).sum();
+            total += extra * energy(r as u8 + 1);
+        }
+        total
     }
+}
 
-    fn valid_moves(&self) -> Vec<State2> {
-        let mut output = vec![];
-        for ridx in 0..4 {
-            // Now enumerate hallway -> room moves
-            // NOTE: IF any of these are possible, they are always the best
-            // move to make, so we won't even bother reporting the others
-            let moves = self.hallway_to_room_moves(ridx);
-            if !moves.is_empty() {
-                for m in moves {
-                    output.push(m);
-                }
-                return output;
-            }
-            // First enumerate room -> hallway moves to the left
-            let moves = self.moves_left(ridx);
-            for m in moves {
-                output.push(m);
-            }
-            // Now enumerate room -> hallway moves to the right
-            let moves = self.moves_right(ridx);
-            for m in moves {
-                output.push(m);
-            }
+/// The room cells of a diagram row, empty for a row of wall.
+fn room_row(idx: usize, line: &str) -> Result<Vec<(usize, char)>, ParseError> {
+    let mut row = vec![];
+    for (col, c) in line.chars().enumerate() {
+        if c != '#' && c != ' ' {
+            cell(idx, col, c)?;
+            row.push((col, c));
         }
-        output
     }
+    Ok(row)
 }
 
-#[aoc(day23, part2)]
-fn part2(input: &[char]) -> usize {
-    let mut modified_input = input.to_vec();
-    let new_letters = vec!['D', 'C', 'B', 'A', 'D', 'B', 'A', 'C'];
-    for x in new_letters.iter().rev() {
-        modified_input.insert(4, *x);
+/// Whether `row` has a cell right in line with each room, which starts
+/// `offset` columns to the right of the hallway position.
+fn check_aligned(
+    idx: usize,
+    line: &str,
+    row: &[(usize, char)],
+    rooms: &[usize],
+    offset: usize,
+) -> Result<(), ParseError> {
+    for (i, &(col, c)) in row.iter().enumerate() {
+        if rooms.get(i).map(|x| x + offset) != Some(col) {
+            return Err(ParseError::invalid_char(
+                idx,
+                col,
+                c,
+                "a wall between rooms",
+            ));
+        }
     }
-    let mut game = Game2::from_letters(&modified_input);
+    if row.len() < rooms.len() {
+        return Err(ParseError::missing(idx, line, "a cell in every room"));
+    }
+    Ok(())
+}
 
-    // Play all possible games
-    while !game.states_to_play.is_empty() {
-        game.step();
+/// Make sure the amphipods and empty room cells in `found` make up exactly
+/// `each` of every kind, one kind per room.
+fn check_counts(
+    found: &[(usize, usize, char)],
+    kinds: usize,
+    each: usize,
+) -> Result<(), ParseError> {
+    let mut counts = vec![0; kinds];
+    for &(idx, col, c) in found {
+        if c == '.' {
+            continue;
+        }
+        let kind = (c as u8 - b'A') as usize;
+        if kind >= kinds {
+            return Err(ParseError::invalid_char(
+                idx,
+                col,
+                c,
+                "one kind of amphipod per room",
+            ));
+        }
+        counts[kind] += 1;
+        if counts[kind] > each {
+            return Err(ParseError::invalid_char(
+                idx,
+                col,
+                c,
+                "no more of a kind than fit in a room",
+            ));
+        }
+    }
+    // Nobody is missing unless there are gaps left in the rooms
+    if counts.iter().any(|&n| n < each) {
+        if let Some(&(idx, col, c)) = found.iter().find(|f| f.2 == '.') {
+            return Err(ParseError::invalid_char(
+                idx,
+                col,
+                c,
+                "enough amphipods to fill the rooms",
+            ));
+        }
     }
-    game.best_score
+    Ok(())
+}
+
+fn cell(idx: usize, col: usize, c: char) -> Result<u8, ParseError> {
+    match c {
+        '.' => Ok(EMPTY),
+        'A'..='Z' => Ok(c as u8 - b'A' + 1),
+        _ => Err(ParseError::invalid_char(idx, col, c, "an amphipod or '.'")),
+    }
+}
+
+/// Where the one amphipod that moved between `a` and `b` came from and went.
+fn changed(a: &State, b: &State) -> (usize, usize) {
+    let from = (0..a.0.len()).find(|&i| a.0[i] != EMPTY && b.0[i] == EMPTY);
+    let to = (0..a.0.len()).find(|&i| a.0[i] == EMPTY && b.0[i] != EMPTY);
+    (from.unwrap(), to.unwrap())
+}
+
+fn energy(kind: u8) -> usize {
+    10usize.pow(kind as u32 - 1)
+}
+
+#[aoc_generator(day23)]
+pub fn load_input(input: &str) -> Result<Burrow, ParseError> {
+    Burrow::parse(input)
+}
+
+#[aoc(day23, part1)]
+pub fn part1(input: &Burrow) -> Result<usize, SolveError> {
+    input.solve().ok_or(SolveError::NoSolution)
+}
+
+#[aoc(day23, part2)]
+pub fn part2(input: &Burrow) -> Result<usize, SolveError> {
+    let unfolded = input.unfold(1, &input.indent(PART2_ROWS))?;
+    unfolded.solve().ok_or(SolveError::NoSolution)
 }
 
 #[cfg(test)]
@@ -672,14 +681,137 @@ mod test {
     #[test]
     fn test_part1() {
         let input = read_to_string("input/2021/23.txt").unwrap();
-        let input = load_input(&input);
-        assert_eq!(part1(&input), 12521);
+        let input = load_input(&input).unwrap();
+        assert_eq!(part1(&input), Ok(12521));
     }
 
     #[test]
     fn test_part2() {
         let input = read_to_string("input/2021/23.txt").unwrap();
-        let input = load_input(&input);
-        assert_eq!(part2(&input), 44169);
+        let input = load_input(&input).unwrap();
+        assert_eq!(part2(&input), Ok(44169));
+    }
+
+    #[test]
+    fn test_other_shapes() {
+        // Three kinds, a longer hallway and rooms three deep
+        let burrow =
+            Burrow::parse("###########\n#.........#\n###B#A#C###\n  #A#B#C#\n  #A#B#C#\n  #######")
+                .unwrap();
+        assert_eq!((burrow.rooms(), burrow.depth()), (3, 3));
+        assert_eq!(burrow.solve(), Some(46));
+
+        // Trailing spaces are fine on any line
+        let padded = "#######  \n#.....#  \n###A#B###\n  #A#B#  \n  #####  ";
+        assert_eq!(Burrow::parse(padded).unwrap().solve(), Some(0));
+
+        // Already sorted
+        let burrow = Burrow::parse("#######\n#.....#\n###A#B###\n  #A#B#\n  #####").unwrap();
+        assert_eq!(burrow.solve(), Some(0));
+
+        // Someone waiting in the hallway
+        let burrow = Burrow::parse("########\n#.....B#\n###A#.####\n  #A#B#\n  ######").unwrap();
+        assert_eq!(burrow.solve(), Some(20));
+
+        assert!(Burrow::parse("#####\n#...#\n###E#\n  ###").is_err());
+
+        // D and A are each in the other's way, so nobody gets home
+        let deadlocked =
+            Burrow::parse("#############\n#.....D.A...#\n###B#C#B#.###\n  #A#D#C#.#\n  #########")
+                .unwrap();
+        assert_eq!(part1(&deadlocked), Err(SolveError::NoSolution));
+        assert_eq!(part2(&deadlocked), Err(SolveError::NoSolution));
+    }
+
+    #[test]
+    fn test_errors() {
+        let err = |input: &str| Burrow::parse(input).unwrap_err().to_string();
+        assert_eq!(
+            err("#############\n#...........#\n###B#C#B#D###\n  #A#D#C#A#"),
+            "5: expected the bottom wall, found end of input"
+        );
+        assert_eq!(
+            err("#############\n#.........#\n###B#C#B#D###\n  #A#D#C#A#\n  #########"),
+            "2:11: expected a hallway as wide as the top wall, found \"#\""
+        );
+        assert_eq!(
+            err("#############\n#..B........#\n###B#C#.#D###\n  #A#D#C#A#\n  #########"),
+            "2:4: expected nobody outside a room, found \"B\""
+        );
+        assert_eq!(
+            err("#############\n#...........#\n###B#C#B#D###\n  #A#D#CA#\n  #########"),
+            "4:9: expected a wall between rooms, found \"A\""
+        );
+        assert_eq!(
+            err("#############\n#...........#\n###B#C#B#E###\n  #A#D#C#A#\n  #########"),
+            "3:10: expected one kind of amphipod per room, found \"E\""
+        );
+        assert_eq!(
+            err("#############\n#...........#\n###B#C#B#D###\n  #A#D#C#B#\n  #########"),
+            "4:10: expected no more of a kind than fit in a room, found \"B\""
+        );
+        assert_eq!(
+            err("#############\n#...........#\n###B#C#.#D###\n  #A#D#C#A#\n  #########"),
+            "3:8: expected enough amphipods to fill the rooms, found \".\""
+        );
+
+        let input = read_to_string("input/2021/23.txt").unwrap();
+        let burrow = load_input(&input).unwrap();
+        let unfolded = burrow.unfold(2, PART2_ROWS).unwrap();
+        assert_eq!(unfolded.depth(), 4);
+        assert!(unfolded
+            .render()
+            .ends_with("#A#D#C#A#\n  #D#C#B#A#\n  #D#B#A#C#\n  #########"));
+        let err = burrow.unfold(1, "  #D#C#B#A#\n  #D#B#A#A#").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "unfolded rows: 2:10: expected no more of a kind than fit in a room, found \"A\""
+        );
+        assert_eq!(
+            burrow.unfold(3, PART2_ROWS),
+            Err(UnfoldError::TooDeep { at: 3, depth: 2 })
+        );
+
+        // Rows for an indented diagram are indented the same
+        let indented: String = input.lines().map(|line| format!("   {}\n", line)).collect();
+        let burrow = load_input(&indented).unwrap();
+        let unfolded = burrow.unfold(1, &burrow.indent(PART2_ROWS)).unwrap();
+        assert_eq!(unfolded.depth(), 4);
+        assert_eq!(part2(&burrow), Ok(44169));
+        let err = burrow.unfold(1, PART2_ROWS).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "unfolded rows: 1:4: expected a wall between rooms, found \"D\""
+        );
+    }
+
+    #[test]
+    fn test_moves() {
+        let input = read_to_string("input/2021/23.txt").unwrap();
+        let burrow = load_input(&input).unwrap();
+        assert_eq!(burrow.render(), input.trim_end());
+
+        let moves = burrow.best_moves().unwrap();
+        assert_eq!(moves.iter().map(|m| m.cost).sum::<usize>(), 12521);
+        let first = Move {
+            amphipod: 'B',
+            from: Spot::Room(2, 0),
+            to: Spot::Hallway(3),
+            cost: 40,
+        };
+        assert_eq!(
+            first.to_string(),
+            "B from room C depth 0 to hallway 3 (40 energy)"
+        );
+
+        let frames = burrow.frames(&moves);
+        assert_eq!(frames.len(), moves.len() + 1);
+        let last = frames[moves.len()]
+            .replace(&format!("{}{}", style::Bold, color::Fg(color::Red)), "")
+            .replace(&format!("{}{}", color::Fg(color::Reset), style::Reset), "");
+        assert_eq!(
+            last,
+            "#############\n#...........#\n###A#B#C#D###\n  #A#B#C#D#\n  #########"
+        );
     }
 }
@@ -1,4 +1,5 @@
-/* This one boiled down to reversing the input.  My rules were:
+/* This one boiled down to reversing the input.  My rules were, as printed by
+ * `aoc2021 alu constraints -i input/2021/day24.txt`:
  *
  * w5=w4
  * w7=w6-4
@@ -12,6 +13,14 @@
  * input = [w0 w1 w2 w3 w4 w5 w6 w7 w8 w9 w10 w11 w12 w13]
  */
 
+use crate::error::{next_token, ParseError};
+use rayon::prelude::*;
+use std::collections::{BTreeSet, HashSet};
+use std::error::Error;
+use std::fmt;
+use std::mem;
+use termion::{color, style};
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 enum InstructionType {
     Inp,
@@ -37,40 +46,101 @@ enum B {
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
-struct Instruction {
+pub struct Instruction {
     itype: InstructionType,
     a: Register,
     b: Option<B>,
 }
 
+impl fmt::Display for Register {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Register::X => "x",
+            Register::Y => "y",
+            Register::Z => "z",
+            Register::W => "w",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self.itype {
+            InstructionType::Inp => "inp",
+            InstructionType::Add => "add",
+            InstructionType::Mul => "mul",
+            InstructionType::Div => "div",
+            InstructionType::Mod => "mod",
+            InstructionType::Eql => "eql",
+        };
+        write!(f, "{} {}", name, self.a)?;
+        match self.b {
+            Some(B::Reg(reg)) => write!(f, " {}", reg),
+            Some(B::Literal(value)) => write!(f, " {}", value),
+            None => Ok(()),
+        }
+    }
+}
+
+fn parse_register(
+    idx: usize,
+    line: &str,
+    token: &str,
+    expected: &'static str,
+) -> Result<Register, ParseError> {
+    match token {
+        "x" => Ok(Register::X),
+        "y" => Ok(Register::Y),
+        "z" => Ok(Register::Z),
+        "w" => Ok(Register::W),
+        _ => Err(ParseError::invalid(idx, line, token, expected)),
+    }
+}
+
 impl Instruction {
-    fn new(itype: InstructionType, a_input: char, b_input: &str) -> Instruction {
-        let a = match a_input {
-            'x' => Register::X,
-            'y' => Register::Y,
-            'z' => Register::Z,
-            'w' => Register::W,
-            _ => panic!("Invalid 'a' char!"),
+    fn parse(idx: usize, line: &str) -> Result<Instruction, ParseError> {
+        let mut liter = line.split(' ');
+        let itypestr = next_token(idx, line, &mut liter, "an instruction")?;
+        let itype = match itypestr {
+            "inp" => InstructionType::Inp,
+            "add" => InstructionType::Add,
+            "mul" => InstructionType::Mul,
+            "div" => InstructionType::Div,
+            "mod" => InstructionType::Mod,
+            "eql" => InstructionType::Eql,
+            _ => return Err(ParseError::invalid(idx, line, itypestr, "an instruction")),
         };
 
-        let mut b = None;
-        if !b_input.is_empty() {
-            if let Ok(bint) = b_input.parse::<i64>() {
-                // Literal
-                b = Some(B::Literal(bint));
-            } else {
-                // Register
-                b = match b_input {
-                    "x" => Some(B::Reg(Register::X)),
-                    "y" => Some(B::Reg(Register::Y)),
-                    "z" => Some(B::Reg(Register::Z)),
-                    "w" => Some(B::Reg(Register::W)),
-                    _ => panic!("Invalid 'b' char!"),
+        let astr = next_token(idx, line, &mut liter, "a register")?;
+        let a = parse_register(idx, line, astr, "a register")?;
+
+        let b = match (itype, liter.next()) {
+            (InstructionType::Inp, None) => None,
+            (InstructionType::Inp, Some(extra)) => {
+                return Err(ParseError::invalid(idx, line, extra, "end of line"))
+            }
+            (_, None) => return Err(ParseError::missing(idx, line, "a register or number")),
+            (_, Some(bstr)) => {
+                if let Ok(bint) = bstr.parse::<i64>() {
+                    // Literal
+                    Some(B::Literal(bint))
+                } else {
+                    // Register
+                    Some(B::Reg(parse_register(
+                        idx,
+                        line,
+                        bstr,
+                        "a register or number",
+                    )?))
                 }
             }
+        };
+        if let Some(extra) = liter.next() {
+            return Err(ParseError::invalid(idx, line, extra, "end of line"));
         }
 
-        Instruction { itype, a, b }
+        Ok(Instruction { itype, a, b })
     }
 }
 
@@ -98,9 +168,6 @@ impl ALU {
     }
 
     fn execute(&mut self, inst: &Instruction) {
-        //println!("Instruction: {}", self.counter);
-        //println!("  {:?}", inst);
-        //println!("  {:?}", self);
         match inst.itype {
             InstructionType::Inp => {
                 let input = self._input.pop();
@@ -133,10 +200,10 @@ impl ALU {
                     };
 
                     match inst.a {
-                        Register::X => self.x += bnum,
-                        Register::Y => self.y += bnum,
-                        Register::Z => self.z += bnum,
-                        Register::W => self.w += bnum,
+                        Register::X => self.x = self.x.checked_add(bnum).expect("Overflow!"),
+                        Register::Y => self.y = self.y.checked_add(bnum).expect("Overflow!"),
+                        Register::Z => self.z = self.z.checked_add(bnum).expect("Overflow!"),
+                        Register::W => self.w = self.w.checked_add(bnum).expect("Overflow!"),
                     };
                 } else {
                     panic!("No b passed!");
@@ -160,10 +227,10 @@ impl ALU {
                     };
 
                     match inst.a {
-                        Register::X => self.x *= bnum,
-                        Register::Y => self.y *= bnum,
-                        Register::Z => self.z *= bnum,
-                        Register::W => self.w *= bnum,
+                        Register::X => self.x = self.x.checked_mul(bnum).expect("Overflow!"),
+                        Register::Y => self.y = self.y.checked_mul(bnum).expect("Overflow!"),
+                        Register::Z => self.z = self.z.checked_mul(bnum).expect("Overflow!"),
+                        Register::W => self.w = self.w.checked_mul(bnum).expect("Overflow!"),
                     };
                 } else {
                     panic!("No b passed!");
@@ -187,15 +254,14 @@ impl ALU {
                     };
 
                     if bnum == 0 {
-                        println!("PC: {}", self.counter);
                         panic!("Cannot divide by 0!");
                     }
 
                     match inst.a {
-                        Register::X => self.x /= bnum,
-                        Register::Y => self.y /= bnum,
-                        Register::Z => self.z /= bnum,
-                        Register::W => self.w /= bnum,
+                        Register::X => self.x = self.x.checked_div(bnum).expect("Overflow!"),
+                        Register::Y => self.y = self.y.checked_div(bnum).expect("Overflow!"),
+                        Register::Z => self.z = self.z.checked_div(bnum).expect("Overflow!"),
+                        Register::W => self.w = self.w.checked_div(bnum).expect("Overflow!"),
                     };
                 } else {
                     panic!("No b passed!");
@@ -219,7 +285,6 @@ impl ALU {
                     };
 
                     if bnum <= 0 {
-                        println!("PC: {}", self.counter);
                         panic!("Cannot mod by b <= 0!");
                     }
 
@@ -288,6 +353,36 @@ impl ALU {
         self.counter += 1;
     }
 
+    /// Why running `inst` now would crash, if it would.
+    fn fault(&self, inst: &Instruction) -> Option<&'static str> {
+        let b = match inst.b {
+            Some(B::Reg(reg)) => self.registers()[register_index(reg)],
+            Some(B::Literal(value)) => value,
+            None if self._input.is_empty() => return Some("out of input"),
+            None => return None,
+        };
+        let a = self.registers()[register_index(inst.a)];
+        let result = match inst.itype {
+            InstructionType::Div if b == 0 => return Some("division by 0"),
+            InstructionType::Mod if b <= 0 => return Some("mod by a number below 1"),
+            InstructionType::Add => a.checked_add(b),
+            InstructionType::Mul => a.checked_mul(b),
+            InstructionType::Div => a.checked_div(b),
+            _ => Some(0),
+        };
+        result.is_none().then_some("overflow")
+    }
+
+    /// Same as `execute`, but refuses to run an instruction that would crash
+    /// or overflow.
+    fn try_execute(&mut self, inst: &Instruction) -> Result<(), &'static str> {
+        if let Some(reason) = self.fault(inst) {
+            return Err(reason);
+        }
+        self.execute(inst);
+        Ok(())
+    }
+
     fn execute_program(&mut self, program: &[Instruction], input: String) {
         // We parse the input a digit at a time and reverse the order for our
         // stack based input reader.
@@ -304,72 +399,1322 @@ impl ALU {
         }
     }
 
-    fn clear_input(&mut self) {
-        self.counter = 0;
-        self._input = vec![];
+    fn registers(&self) -> [i64; 4] {
+        [self.x, self.y, self.z, self.w]
+    }
+
+    fn set_registers(&mut self, [x, y, z, w]: [i64; 4]) {
+        self.x = x;
+        self.y = y;
+        self.z = z;
+        self.w = w;
+    }
+}
+
+fn register_index(reg: Register) -> usize {
+    match reg {
+        Register::X => 0,
+        Register::Y => 1,
+        Register::Z => 2,
+        Register::W => 3,
+    }
+}
+
+/// The registers `instructions` read before writing, plus whichever of
+/// `live_out` they leave untouched.
+fn live_in(instructions: &[Instruction], live_out: [bool; 4]) -> [bool; 4] {
+    let mut live = [false; 4];
+    let mut written = [false; 4];
+    for inst in instructions {
+        if let Some(B::Reg(reg)) = inst.b {
+            live[register_index(reg)] |= !written[register_index(reg)];
+        }
+        let a = register_index(inst.a);
+        match (inst.itype, inst.b) {
+            // mul a 0 clears a without caring what was in it
+            (InstructionType::Inp, _) | (InstructionType::Mul, Some(B::Literal(0))) => (),
+            _ => live[a] |= !written[a],
+        }
+        written[a] = true;
+    }
+    for i in 0..4 {
+        live[i] |= live_out[i] && !written[i];
+    }
+    live
+}
+
+/// The instructions from one `inp` up to the next. Only the `live`
+/// registers at the start of the block can change the outcome, so the
+/// search forgets the others and treats states differing only in them as
+/// the same.
+struct Block<'a> {
+    instructions: &'a [Instruction],
+    live: [bool; 4],
+}
+
+fn split_blocks(program: &[Instruction]) -> (&[Instruction], Vec<Block<'_>>) {
+    let mut starts: Vec<_> = (0..program.len())
+        .filter(|&i| program[i].itype == InstructionType::Inp)
+        .collect();
+    let prelude = &program[..starts.first().copied().unwrap_or(program.len())];
+    starts.push(program.len());
+
+    // Only z matters once the program is done
+    let mut live = [false, false, true, false];
+    let mut blocks = vec![];
+    for bounds in starts.windows(2).rev() {
+        let instructions = &program[bounds[0]..bounds[1]];
+        live = live_in(instructions, live);
+        blocks.push(Block { instructions, live });
+    }
+    blocks.reverse();
+    (prelude, blocks)
+}
+
+/// Bounds on what a register might hold.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Range {
+    lo: i64,
+    hi: i64,
+}
+
+impl Range {
+    const ANY: Range = Range {
+        lo: i64::MIN,
+        hi: i64::MAX,
+    };
+    const DIGIT: Range = Range { lo: 1, hi: 9 };
+
+    fn exact(value: i64) -> Range {
+        Range {
+            lo: value,
+            hi: value,
+        }
+    }
+
+    fn contains(&self, value: i64) -> bool {
+        self.lo <= value && value <= self.hi
+    }
+
+    // The extremes of add, mul and div are all at the corners, as long as
+    // div doesn't straddle 0
+    fn corners(a: Range, b: Range, f: impl Fn(i64, i64) -> Option<i64>) -> Range {
+        let corners = [(a.lo, b.lo), (a.lo, b.hi), (a.hi, b.lo), (a.hi, b.hi)];
+        let mut out = Range {
+            lo: i64::MAX,
+            hi: i64::MIN,
+        };
+        for (a, b) in corners {
+            match f(a, b) {
+                Some(v) => {
+                    out.lo = out.lo.min(v);
+                    out.hi = out.hi.max(v);
+                }
+                None => return Range::ANY,
+            }
+        }
+        out
+    }
+
+    /// Everything `itype` could leave in its first operand.
+    fn apply(itype: InstructionType, a: Range, b: Range) -> Range {
+        match itype {
+            InstructionType::Inp => Range::DIGIT,
+            InstructionType::Add => Range::corners(a, b, i64::checked_add),
+            InstructionType::Mul => Range::corners(a, b, i64::checked_mul),
+            InstructionType::Div if b.contains(0) => Range::ANY,
+            InstructionType::Div => Range::corners(a, b, i64::checked_div),
+            // Crashes the ALU, the search rejects whatever gets here
+            InstructionType::Mod if b.lo <= 0 || a.lo < 0 => Range::ANY,
+            InstructionType::Mod => {
+                let (lo, hi) = (a.lo % b.lo, a.hi % b.lo);
+                if b.lo == b.hi && a.hi - a.lo < b.lo && lo <= hi {
+                    Range { lo, hi }
+                } else {
+                    Range {
+                        lo: 0,
+                        hi: a.hi.min(b.hi - 1),
+                    }
+                }
+            }
+            InstructionType::Eql if a.hi < b.lo || b.hi < a.lo => Range::exact(0),
+            InstructionType::Eql if a.lo == a.hi && a == b => Range::exact(1),
+            InstructionType::Eql => Range { lo: 0, hi: 1 },
+        }
+    }
+}
+
+/// Whether z could still end up 0 running `blocks` from `registers`, with
+/// every input anywhere from 1 to 9. Cheap enough to run on every state the
+/// search visits, and it cuts off the states with z too big to ever come
+/// back down.
+fn can_reach_zero(blocks: &[Block], registers: [i64; 4]) -> bool {
+    let mut ranges = registers.map(Range::exact);
+    for inst in blocks.iter().flat_map(|block| block.instructions) {
+        let b = match inst.b {
+            Some(B::Reg(reg)) => ranges[register_index(reg)],
+            Some(B::Literal(value)) => Range::exact(value),
+            None => Range::DIGIT,
+        };
+        let a = register_index(inst.a);
+        ranges[a] = Range::apply(inst.itype, ranges[a], b);
+    }
+    ranges[2].contains(0)
+}
+
+fn search(
+    blocks: &[Block],
+    registers: [i64; 4],
+    digits: &[i64],
+    dead: &mut HashSet<(usize, [i64; 4])>,
+    number: &mut Vec<i64>,
+) -> bool {
+    let index = number.len();
+    let Some(block) = blocks.get(index) else {
+        return registers[2] == 0;
+    };
+    let mut key = registers;
+    for (value, live) in key.iter_mut().zip(block.live) {
+        if !live {
+            *value = 0;
+        }
+    }
+    if dead.contains(&(index, key)) || !can_reach_zero(&blocks[index..], key) {
+        return false;
+    }
+
+    let mut alu = ALU::new();
+    for &digit in digits {
+        alu.set_registers(key);
+        alu._input = vec![digit];
+        // A digit that crashes the ALU is as good as a rejected one
+        if block
+            .instructions
+            .iter()
+            .any(|inst| alu.try_execute(inst).is_err())
+        {
+            continue;
+        }
+        number.push(digit);
+        if search(blocks, alu.registers(), digits, dead, number) {
+            return true;
+        }
+        number.pop();
     }
+    dead.insert((index, key));
+    false
+}
+
+/// The largest or smallest input, one digit from 1 to 9 per `inp`, that
+/// leaves z at 0. Searches depth first one block at a time, remembering
+/// every block and register state that turned out to be a dead end.
+pub fn find_model_number(program: &[Instruction], largest: bool) -> Option<u64> {
+    let (prelude, blocks) = split_blocks(program);
+    let mut alu = ALU::new();
+    for inst in prelude {
+        alu.try_execute(inst).ok()?;
+    }
+
+    let mut digits: Vec<i64> = (1..=9).collect();
+    if largest {
+        digits.reverse();
+    }
+    let mut number = vec![];
+    if search(
+        &blocks,
+        alu.registers(),
+        &digits,
+        &mut HashSet::new(),
+        &mut number,
+    ) {
+        Some(number.iter().fold(0, |n, &d| n * 10 + d as u64))
+    } else {
+        None
+    }
+}
+
+/// Run the whole program on a model number and check it's accepted.
+fn is_valid(program: &[Instruction], number: u64) -> bool {
+    let mut alu = ALU::new();
+    alu.execute_program(program, number.to_string());
+    alu.z == 0
 }
 
 #[aoc_generator(day24)]
-fn load_input(input: &str) -> Vec<Instruction> {
+pub fn load_input(input: &str) -> Result<Vec<Instruction>, ParseError> {
     let mut output = vec![];
-    for line in input.lines() {
-        let mut liter = line.split(' ');
-        let itypestr = liter.next().unwrap();
-        let astr = liter.next().unwrap();
-        let bstr = liter.next();
+    for (idx, line) in input.lines().enumerate() {
+        output.push(Instruction::parse(idx, line)?);
+    }
+    Ok(output)
+}
+
+/// A register's value as a function of the input digits `w0`, `w1`, ...
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+enum Expr {
+    Const(i64),
+    Digit(usize),
+    Op(InstructionType, Box<Expr>, Box<Expr>),
+}
 
-        let b;
-        if let Some(value) = bstr {
-            b = value;
+impl Expr {
+    fn range(&self) -> Range {
+        match self {
+            Expr::Const(value) => Range::exact(*value),
+            Expr::Digit(_) => Range::DIGIT,
+            Expr::Op(itype, a, b) => Range::apply(*itype, a.range(), b.range()),
+        }
+    }
+
+    /// `q` and `r` with `self = q * d + r`, when that makes `q` the
+    /// quotient and `r` the remainder of dividing by `d`.
+    fn split(&self, d: i64) -> Option<(&Expr, &Expr)> {
+        let (q, r): (&Expr, &Expr) = match self {
+            Expr::Op(InstructionType::Add, a, r) => match a.as_ref() {
+                Expr::Op(InstructionType::Mul, q, m) if **m == Expr::Const(d) => (q, r),
+                _ => return None,
+            },
+            Expr::Op(InstructionType::Mul, q, m) if **m == Expr::Const(d) => (q, &ZERO),
+            _ => return None,
+        };
+        let r_range = r.range();
+        if q.range().lo >= 0 && r_range.lo >= 0 && r_range.hi < d {
+            Some((q, r))
         } else {
-            b = "";
+            None
         }
+    }
 
-        let itype = match itypestr {
-            "inp" => InstructionType::Inp,
-            "add" => InstructionType::Add,
-            "mul" => InstructionType::Mul,
-            "div" => InstructionType::Div,
-            "mod" => InstructionType::Mod,
-            "eql" => InstructionType::Eql,
-            _ => panic!("What even is this"),
+    /// Build `a <itype> b`, simplified as far as the value ranges allow.
+    /// Constants end up on the right.
+    fn op(itype: InstructionType, a: Expr, b: Expr) -> Expr {
+        let range = Range::apply(itype, a.range(), b.range());
+        if range.lo == range.hi {
+            return Expr::Const(range.lo);
+        }
+        match (itype, &a, &b) {
+            (InstructionType::Add, _, Expr::Const(0)) => a,
+            (InstructionType::Mul | InstructionType::Div, _, Expr::Const(1)) => a,
+            (InstructionType::Add | InstructionType::Mul, Expr::Const(_), _) => {
+                Expr::op(itype, b, a)
+            }
+            (InstructionType::Add, Expr::Op(InstructionType::Add, e, c), Expr::Const(d)) => {
+                match c.as_ref() {
+                    Expr::Const(c) => Expr::op(itype, (**e).clone(), Expr::Const(c + d)),
+                    _ => Expr::Op(itype, Box::new(a), Box::new(b)),
+                }
+            }
+            (InstructionType::Div, _, Expr::Const(d)) if a.split(*d).is_some() => {
+                a.split(*d).unwrap().0.clone()
+            }
+            (InstructionType::Mod, _, Expr::Const(m)) if a.range().lo >= 0 && a.range().hi < *m => {
+                a
+            }
+            (InstructionType::Mod, _, Expr::Const(m)) if a.split(*m).is_some() => {
+                a.split(*m).unwrap().1.clone()
+            }
+            (InstructionType::Eql, _, _) if a == b => Expr::Const(1),
+            _ => Expr::Op(itype, Box::new(a), Box::new(b)),
+        }
+    }
+
+    // Chains like `a + b + c` only need parentheses on the right
+    fn fmt_operand(
+        &self,
+        f: &mut fmt::Formatter,
+        parent: InstructionType,
+        left: bool,
+    ) -> fmt::Result {
+        match self {
+            Expr::Op(itype, ..) if !left || *itype != parent => write!(f, "({})", self),
+            _ => write!(f, "{}", self),
+        }
+    }
+}
+
+const ZERO: Expr = Expr::Const(0);
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Expr::Const(value) => write!(f, "{}", value),
+            Expr::Digit(idx) => write!(f, "w{}", idx),
+            Expr::Op(InstructionType::Add, a, b)
+                if b.range().hi < 0 && b.range().lo == b.range().hi =>
+            {
+                a.fmt_operand(f, InstructionType::Add, true)?;
+                write!(f, " - {}", -b.range().lo)
+            }
+            Expr::Op(itype, a, b) => {
+                let symbol = match itype {
+                    InstructionType::Add => "+",
+                    InstructionType::Mul => "*",
+                    InstructionType::Div => "/",
+                    InstructionType::Mod => "%",
+                    InstructionType::Eql => "==",
+                    InstructionType::Inp => unreachable!("inp doesn't compute anything"),
+                };
+                a.fmt_operand(f, *itype, true)?;
+                write!(f, " {} ", symbol)?;
+                b.fmt_operand(f, *itype, false)
+            }
+        }
+    }
+}
+
+/// Something the input digits have to satisfy to get down one path through
+/// the program.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Constraint {
+    lhs: Expr,
+    rhs: Expr,
+    equal: bool,
+}
+
+impl Constraint {
+    /// `(i, j, k)` when the constraint says `wi = wj + k` with `i > j`.
+    pub fn as_pair(&self) -> Option<(usize, usize, i64)> {
+        let linear = |e: &Expr| match e {
+            Expr::Digit(i) => Some((*i, 0)),
+            Expr::Op(InstructionType::Add, a, b) => match (a.as_ref(), b.as_ref()) {
+                (Expr::Digit(i), Expr::Const(k)) => Some((*i, *k)),
+                _ => None,
+            },
+            _ => None,
         };
+        if !self.equal {
+            return None;
+        }
+        // i + ki = j + kj
+        let ((i, ki), (j, kj)) = (linear(&self.lhs)?, linear(&self.rhs)?);
+        match i.cmp(&j) {
+            std::cmp::Ordering::Greater => Some((i, j, kj - ki)),
+            std::cmp::Ordering::Less => Some((j, i, ki - kj)),
+            std::cmp::Ordering::Equal => None,
+        }
+    }
+}
 
-        let inst = Instruction::new(itype, astr.chars().next().unwrap(), b);
-        output.push(inst);
+/// `wi=wj+k`, the way the comment at the top writes them.
+fn format_pair(i: usize, j: usize, k: i64) -> String {
+    match k {
+        0 => format!("w{}=w{}", i, j),
+        k if k < 0 => format!("w{}=w{}-{}", i, j, -k),
+        k => format!("w{}=w{}+{}", i, j, k),
     }
-    output
 }
 
-#[aoc(day24, part1)]
-fn part1(input: &[Instruction]) -> usize {
-    let mut alu = ALU::new();
-    let input_num = 53999995829399;
-    let input_str = input_num.to_string();
-    if !input_str.contains('0') {
-        alu.clear_input();
-        alu.execute_program(input, input_str);
-        if alu.z == 0 {
-            println!("FOUND IT");
-            return input_num;
+impl fmt::Display for Constraint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.as_pair() {
+            Some((i, j, k)) => write!(f, "{}", format_pair(i, j, k)),
+            None if self.equal => write!(f, "{} = {}", self.lhs, self.rhs),
+            None => write!(f, "{} != {}", self.lhs, self.rhs),
+        }
+    }
+}
+
+/// Give up rather than follow more paths than this at once.
+const MAX_PATHS: usize = 1 << 16;
+
+/// An ALU whose registers hold expressions over the input digits. Each
+/// `eql` the ranges can't decide splits it in two, one assuming the
+/// operands are equal and one that they aren't.
+#[derive(Clone, Debug)]
+struct SymbolicALU {
+    registers: [Expr; 4],
+    inputs: usize,
+    constraints: Vec<Constraint>,
+}
+
+impl SymbolicALU {
+    fn new() -> SymbolicALU {
+        SymbolicALU {
+            registers: [ZERO; 4],
+            inputs: 0,
+            constraints: vec![],
         }
     }
-    panic!("Shouldn't get here");
+
+    fn execute(mut self, inst: &Instruction, paths: &mut Vec<SymbolicALU>) {
+        let a = register_index(inst.a);
+        let b = match inst.b {
+            Some(B::Reg(reg)) => self.registers[register_index(reg)].clone(),
+            Some(B::Literal(value)) => Expr::Const(value),
+            None => Expr::Digit(self.inputs),
+        };
+        let lhs = mem::replace(&mut self.registers[a], ZERO);
+        match Expr::op(inst.itype, lhs.clone(), b.clone()) {
+            _ if inst.itype == InstructionType::Inp => {
+                self.registers[a] = b;
+                self.inputs += 1;
+            }
+            Expr::Op(InstructionType::Eql, ..) => {
+                for equal in [true, false] {
+                    let mut path = self.clone();
+                    path.registers[a] = Expr::Const(equal as i64);
+                    path.constraints.push(Constraint {
+                        lhs: lhs.clone(),
+                        rhs: b.clone(),
+                        equal,
+                    });
+                    paths.push(path);
+                }
+                return;
+            }
+            value => self.registers[a] = value,
+        }
+        paths.push(self);
+    }
+}
+
+/// Run `program` symbolically and return the constraints on the digits for
+/// every path that can end with z = 0, or `None` if there are too many
+/// paths to follow.
+pub fn digit_constraints(program: &[Instruction]) -> Option<Vec<Vec<Constraint>>> {
+    let mut paths = vec![SymbolicALU::new()];
+    for inst in program {
+        let mut next = vec![];
+        for path in paths {
+            path.execute(inst, &mut next);
+        }
+        if next.len() > MAX_PATHS {
+            return None;
+        }
+        paths = next;
+    }
+
+    let mut accepted = vec![];
+    for mut path in paths {
+        let z = mem::replace(&mut path.registers[2], ZERO);
+        if !z.range().contains(0) {
+            continue;
+        }
+        if z != ZERO {
+            path.constraints.push(Constraint {
+                lhs: z,
+                rhs: ZERO,
+                equal: true,
+            });
+        }
+        accepted.push(path.constraints);
+    }
+    Some(accepted)
+}
+
+/// The largest and smallest model numbers allowed by `constraints`, if all
+/// of them tie one digit to another as `wi = wj + k`.
+pub fn solve_constraints(constraints: &[Constraint], digits: usize) -> Option<(u64, u64)> {
+    let mut largest = vec![9; digits];
+    let mut smallest = vec![1; digits];
+    let mut paired = vec![false; digits];
+    for constraint in constraints {
+        let (i, j, k) = constraint.as_pair()?;
+        if i >= digits || k.abs() > 8 || paired[i] || paired[j] {
+            return None;
+        }
+        paired[i] = true;
+        paired[j] = true;
+        largest[j] = 9.min(9 - k);
+        largest[i] = largest[j] + k;
+        smallest[j] = 1.max(1 - k);
+        smallest[i] = smallest[j] + k;
+    }
+    let number = |digits: Vec<i64>| digits.iter().fold(0, |n, &d| n * 10 + d as u64);
+    Some((number(largest), number(smallest)))
+}
+
+/// How many digits `program` reads.
+pub fn input_count(program: &[Instruction]) -> usize {
+    program
+        .iter()
+        .filter(|inst| inst.itype == InstructionType::Inp)
+        .count()
+}
+
+/// Every block of a MONAD program is this, apart from the three blanks.
+const MONAD_BLOCK: &str = "\
+inp w
+mul x 0
+add x z
+mod x 26
+div z _
+add x _
+eql x w
+eql x 0
+mul y 0
+add y 25
+mul y x
+add y 1
+mul z y
+mul y 0
+add y w
+add y _
+mul y x
+add z y";
+
+/// The numbers filling the blanks in one block of a MONAD program, which
+/// boils down to
+///
+/// ```text
+/// w = next digit
+/// x = z % 26 + check
+/// z = z / div
+/// if x != w { z = z * 26 + w + offset }
+/// ```
+///
+/// Treating z as a stack of base 26 digits, blocks with `div` 1 push, and
+/// those with `div` 26 pop and only push back if the digits don't line up.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MonadBlock {
+    pub div: i64,
+    pub check: i64,
+    pub offset: i64,
+}
+
+/// Where a program stops looking like MONAD.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NotMonad {
+    pub block: usize,
+    pub line: usize,
+    pub found: Option<Instruction>,
+}
+
+impl fmt::Display for NotMonad {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let expected = MONAD_BLOCK.lines().nth(self.line).unwrap();
+        match self.found {
+            Some(inst) => write!(
+                f,
+                "block {} line {}: expected {}, found {}",
+                self.block, self.line, expected, inst
+            ),
+            None => write!(
+                f,
+                "block {} line {}: expected {}, found the end of the program",
+                self.block, self.line, expected
+            ),
+        }
+    }
+}
+
+impl Error for NotMonad {}
+
+/// Pick the blocks of a MONAD program apart.
+pub fn recognize_monad(program: &[Instruction]) -> Result<Vec<MonadBlock>, NotMonad> {
+    let template: Vec<_> = MONAD_BLOCK.lines().collect();
+    let mut blocks = vec![];
+    let mut iter = program.iter();
+    loop {
+        let block = blocks.len();
+        let mut blanks = vec![];
+        for (line, pattern) in template.iter().enumerate() {
+            let not_monad = |found: Option<&Instruction>| NotMonad {
+                block,
+                line,
+                found: found.copied(),
+            };
+            let inst = match iter.next() {
+                Some(inst) => inst,
+                None if line == 0 && block > 0 => return Ok(blocks),
+                None => return Err(not_monad(None)),
+            };
+            let text = inst.to_string();
+            match (pattern.strip_suffix('_'), inst.b) {
+                (Some(prefix), Some(B::Literal(value))) if text.starts_with(prefix) => {
+                    blanks.push(value)
+                }
+                (None, _) if text == *pattern => (),
+                _ => return Err(not_monad(Some(inst))),
+            }
+        }
+        blocks.push(MonadBlock {
+            div: blanks[0],
+            check: blanks[1],
+            offset: blanks[2],
+        });
+    }
+}
+
+/// Match pushes to pops, giving `(i, j, k)` for each popping block `i` that
+/// has to satisfy `wi = wj + k` to keep z from growing. `None` if the blocks
+/// don't pair off like that.
+pub fn monad_pairs(blocks: &[MonadBlock]) -> Option<Vec<(usize, usize, i64)>> {
+    let mut stack = vec![];
+    let mut pairs = vec![];
+    for (i, block) in blocks.iter().enumerate() {
+        match block.div {
+            1 => stack.push(i),
+            26 => {
+                let j = stack.pop()?;
+                pairs.push((i, j, blocks[j].offset + block.check));
+            }
+            _ => return None,
+        }
+    }
+    if stack.is_empty() {
+        Some(pairs)
+    } else {
+        None
+    }
+}
+
+/// One line per block with its parameters and what it does to the stack,
+/// indented by how deep the stack is at that point.
+pub fn monad_listing(blocks: &[MonadBlock]) -> String {
+    let mut lines = vec![String::from("     div  check  offset")];
+    let mut stack = vec![];
+    for (i, block) in blocks.iter().enumerate() {
+        let action = if block.div == 1 {
+            stack.push(i);
+            format!("push w{} + {}", i, block.offset)
+        } else if let Some(j) = stack.pop() {
+            format!("pop  {}", format_pair(i, j, blocks[j].offset + block.check))
+        } else {
+            String::from("pop  (empty stack)")
+        };
+        let depth = stack.len() - (block.div == 1) as usize;
+        lines.push(format!(
+            "{:>2}  {:>4}  {:>5}  {:>6}  {}{}",
+            i,
+            block.div,
+            block.check,
+            block.offset,
+            "| ".repeat(depth),
+            action
+        ));
+    }
+    lines.join("\n")
+}
+
+/// One instruction of a compiled program, with registers as indices and
+/// literal operands split out so running it never has to look at a `B`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Op {
+    Inp(usize),
+    Set(usize, i64),
+    Copy(usize, usize),
+    Add(usize, usize),
+    AddI(usize, i64),
+    Mul(usize, usize),
+    MulI(usize, i64),
+    Div(usize, usize),
+    DivI(usize, i64),
+    Mod(usize, usize),
+    ModI(usize, i64),
+    Eql(usize, usize),
+    EqlI(usize, i64),
+    /// A literal divisor of 0, or anything else that always crashes.
+    Fault,
+}
+
+impl Op {
+    fn new(inst: &Instruction) -> Op {
+        let a = register_index(inst.a);
+        match (inst.itype, inst.b) {
+            (InstructionType::Inp, _) => Op::Inp(a),
+            (InstructionType::Mul, Some(B::Literal(0))) => Op::Set(a, 0),
+            (InstructionType::Div, Some(B::Literal(0))) => Op::Fault,
+            (InstructionType::Mod, Some(B::Literal(v))) if v <= 0 => Op::Fault,
+            (itype, Some(B::Literal(v))) => match itype {
+                InstructionType::Add => Op::AddI(a, v),
+                InstructionType::Mul => Op::MulI(a, v),
+                InstructionType::Div => Op::DivI(a, v),
+                InstructionType::Mod => Op::ModI(a, v),
+                _ => Op::EqlI(a, v),
+            },
+            (itype, Some(B::Reg(reg))) => {
+                let b = register_index(reg);
+                match itype {
+                    InstructionType::Add => Op::Add(a, b),
+                    InstructionType::Mul => Op::Mul(a, b),
+                    InstructionType::Div => Op::Div(a, b),
+                    InstructionType::Mod => Op::Mod(a, b),
+                    _ => Op::Eql(a, b),
+                }
+            }
+            (_, None) => unreachable!("only inp has no second operand"),
+        }
+    }
+
+    /// The register written, and whether its old value is read too.
+    fn dest(&self) -> Option<(usize, bool)> {
+        match *self {
+            Op::Inp(a) | Op::Set(a, _) | Op::Copy(a, _) => Some((a, false)),
+            Op::Fault => None,
+            Op::Add(a, _)
+            | Op::AddI(a, _)
+            | Op::Mul(a, _)
+            | Op::MulI(a, _)
+            | Op::Div(a, _)
+            | Op::DivI(a, _)
+            | Op::Mod(a, _)
+            | Op::ModI(a, _)
+            | Op::Eql(a, _)
+            | Op::EqlI(a, _) => Some((a, true)),
+        }
+    }
+
+    fn source(&self) -> Option<usize> {
+        match *self {
+            Op::Copy(_, b)
+            | Op::Add(_, b)
+            | Op::Mul(_, b)
+            | Op::Div(_, b)
+            | Op::Mod(_, b)
+            | Op::Eql(_, b) => Some(b),
+            _ => None,
+        }
+    }
+
+    /// Whether running it can crash or overflow, so it has to stay even if its
+    /// result is never used.
+    fn can_fault(&self) -> bool {
+        !matches!(
+            self,
+            Op::Inp(_) | Op::Set(..) | Op::Copy(..) | Op::ModI(..) | Op::Eql(..) | Op::EqlI(..)
+        )
+    }
+}
+
+/// An ALU program compiled for running many inputs fast. Only z is kept
+/// meaningful to the end, everything done just for the other registers is
+/// dropped.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Compiled {
+    code: Vec<Op>,
+    inputs: usize,
+}
+
+/// Compile `program`, folding `mul a 0` followed by `add a ...` into a
+/// single load, then removing every instruction whose result is
+/// overwritten before anything reads it.
+pub fn compile(program: &[Instruction]) -> Compiled {
+    let mut code: Vec<Op> = vec![];
+    for inst in program {
+        let op = Op::new(inst);
+        match (code.last().copied(), op) {
+            (Some(Op::Set(a, v)), Op::AddI(b, w)) if a == b => {
+                *code.last_mut().unwrap() = v.checked_add(w).map_or(Op::Fault, |v| Op::Set(a, v))
+            }
+            (Some(Op::Set(a, 0)), Op::Add(b, c)) if a == b && a != c => {
+                *code.last_mut().unwrap() = Op::Copy(a, c)
+            }
+            (_, Op::AddI(_, 0) | Op::MulI(_, 1) | Op::DivI(_, 1)) => (),
+            _ => code.push(op),
+        }
+    }
+
+    let mut live = [false, false, true, false];
+    let mut kept = vec![];
+    for op in code.into_iter().rev() {
+        if let Some((a, reads)) = op.dest() {
+            // inp has to stay to consume its digit
+            if !live[a] && !op.can_fault() && !matches!(op, Op::Inp(_)) {
+                continue;
+            }
+            live[a] = reads;
+        }
+        if let Some(b) = op.source() {
+            live[b] = true;
+        }
+        kept.push(op);
+    }
+    kept.reverse();
+    Compiled {
+        inputs: input_count(program),
+        code: kept,
+    }
+}
+
+impl Compiled {
+    pub fn len(&self) -> usize {
+        self.code.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.code.is_empty()
+    }
+
+    /// How many digits a run reads.
+    pub fn inputs(&self) -> usize {
+        self.inputs
+    }
+
+    /// Final z after running from registers `start` (x, y, z, w), or `None`
+    /// if the program would crash, overflow or runs out of digits.
+    pub fn run(&self, start: [i64; 4], digits: &[i64]) -> Option<i64> {
+        let mut r = start;
+        let mut digits = digits.iter();
+        for op in &self.code {
+            match *op {
+                Op::Inp(a) => r[a] = *digits.next()?,
+                Op::Set(a, v) => r[a] = v,
+                Op::Copy(a, b) => r[a] = r[b],
+                Op::Add(a, b) => r[a] = r[a].checked_add(r[b])?,
+                Op::AddI(a, v) => r[a] = r[a].checked_add(v)?,
+                Op::Mul(a, b) => r[a] = r[a].checked_mul(r[b])?,
+                Op::MulI(a, v) => r[a] = r[a].checked_mul(v)?,
+                Op::Div(a, b) => r[a] = r[a].checked_div(r[b])?,
+                Op::DivI(a, v) => r[a] = r[a].checked_div(v)?,
+                Op::Mod(_, b) if r[b] <= 0 => return None,
+                Op::Mod(a, b) => r[a] %= r[b],
+                Op::ModI(a, v) => r[a] %= v,
+                Op::Eql(a, b) => r[a] = (r[a] == r[b]) as i64,
+                Op::EqlI(a, v) => r[a] = (r[a] == v) as i64,
+                Op::Fault => return None,
+            }
+        }
+        Some(r[2])
+    }
+
+    /// Whether the program accepts `number`.
+    pub fn accepts(&self, digits: &[i64]) -> bool {
+        self.run([0; 4], digits) == Some(0)
+    }
+}
+
+/// Every number accepted by `compiled` that matches `template` apart from
+/// the digits at `free`, which take every value from 1 to 9. Runs in
+/// parallel, 9^free.len() inputs in all. Sorted smallest first.
+pub fn brute_force(compiled: &Compiled, template: &[i64], free: &[usize]) -> Vec<u64> {
+    let count = 9u64.pow(free.len() as u32);
+    let mut accepted: Vec<u64> = (0..count)
+        .into_par_iter()
+        .map_init(
+            || template.to_vec(),
+            |digits, mut n| {
+                for &idx in free.iter().rev() {
+                    digits[idx] = (n % 9) as i64 + 1;
+                    n /= 9;
+                }
+                if compiled.accepts(digits) {
+                    Some(digits.iter().fold(0, |n, &d| n * 10 + d as u64))
+                } else {
+                    None
+                }
+            },
+        )
+        .flatten()
+        .collect();
+    accepted.sort_unstable();
+    accepted
+}
+
+/// Why the debugger stopped.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Stop {
+    /// Every instruction has run.
+    End,
+    /// Back before the first instruction.
+    Start,
+    Breakpoint(usize),
+    /// The next instruction would crash the ALU.
+    Fault(&'static str),
+}
+
+impl fmt::Display for Stop {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Stop::End => write!(f, "end of program"),
+            Stop::Start => write!(f, "start of program"),
+            Stop::Breakpoint(idx) => write!(f, "breakpoint at {}", idx),
+            Stop::Fault(reason) => write!(f, "can't continue: {}", reason),
+        }
+    }
+}
+
+/// Steps an ALU through a program, keeping a snapshot from before every
+/// instruction so it can run backwards as well as forwards.
+pub struct Debugger<'a> {
+    program: &'a [Instruction],
+    alu: ALU,
+    history: Vec<ALU>,
+    breakpoints: BTreeSet<usize>,
+}
+
+impl<'a> Debugger<'a> {
+    /// `input` holds the digits fed to `inp`, most significant first.
+    pub fn new(program: &'a [Instruction], input: &str) -> Result<Debugger<'a>, ParseError> {
+        let mut alu = ALU::new();
+        for (col, c) in input.trim().chars().enumerate() {
+            let digit = c
+                .to_digit(10)
+                .ok_or_else(|| ParseError::invalid_char(0, col, c, "a digit"))?;
+            alu._input.push(digit as i64);
+        }
+        // Read back to front, like in execute_program
+        alu._input.reverse();
+        Ok(Debugger {
+            program,
+            alu,
+            history: vec![],
+            breakpoints: BTreeSet::new(),
+        })
+    }
+
+    /// Index of the next instruction to run.
+    pub fn pc(&self) -> usize {
+        self.alu.counter
+    }
+
+    pub fn program(&self) -> &[Instruction] {
+        self.program
+    }
+
+    /// x, y, z and w.
+    pub fn registers(&self) -> [i64; 4] {
+        [self.alu.x, self.alu.y, self.alu.z, self.alu.w]
+    }
+
+    /// Input not read yet, next digit first.
+    pub fn remaining_input(&self) -> impl Iterator<Item = i64> + '_ {
+        self.alu._input.iter().rev().copied()
+    }
+
+    pub fn toggle_breakpoint(&mut self, idx: usize) {
+        if !self.breakpoints.remove(&idx) {
+            self.breakpoints.insert(idx);
+        }
+    }
+
+    pub fn breakpoints(&self) -> impl Iterator<Item = usize> + '_ {
+        self.breakpoints.iter().copied()
+    }
+
+    /// Run one instruction.
+    pub fn step(&mut self) -> Result<(), Stop> {
+        let inst = self.program.get(self.pc()).ok_or(Stop::End)?;
+        if let Some(reason) = self.alu.fault(inst) {
+            return Err(Stop::Fault(reason));
+        }
+        self.history.push(self.alu.clone());
+        self.alu.execute(inst);
+        Ok(())
+    }
+
+    /// Undo the last instruction.
+    pub fn step_back(&mut self) -> Result<(), Stop> {
+        self.alu = self.history.pop().ok_or(Stop::Start)?;
+        Ok(())
+    }
+
+    fn at_block_start(&self) -> bool {
+        self.program
+            .get(self.pc())
+            .is_some_and(|inst| inst.itype == InstructionType::Inp)
+    }
+
+    fn check_breakpoint(&self) -> Result<(), Stop> {
+        if self.breakpoints.contains(&self.pc()) {
+            Err(Stop::Breakpoint(self.pc()))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Run up to the next `inp`, or a breakpoint if one comes first.
+    pub fn next_block(&mut self) -> Result<(), Stop> {
+        self.step()?;
+        while !self.at_block_start() {
+            self.check_breakpoint()?;
+            self.step()?;
+        }
+        Ok(())
+    }
+
+    /// Back to the `inp` starting this block, or the previous one if
+    /// already there.
+    pub fn previous_block(&mut self) -> Result<(), Stop> {
+        self.step_back()?;
+        while !self.at_block_start() {
+            self.check_breakpoint()?;
+            self.step_back()?;
+        }
+        Ok(())
+    }
+
+    /// Run until a breakpoint or the end.
+    pub fn resume(&mut self) -> Result<(), Stop> {
+        loop {
+            self.step()?;
+            self.check_breakpoint()?;
+        }
+    }
+
+    /// Run backwards until a breakpoint or the start.
+    pub fn reverse(&mut self) -> Result<(), Stop> {
+        loop {
+            self.step_back()?;
+            self.check_breakpoint()?;
+        }
+    }
+
+    /// A full screen view for a terminal `rows` lines high: registers and
+    /// input on top, then the listing around `cursor`, the next instruction
+    /// highlighted and breakpoints in red.
+    pub fn render(&self, cursor: usize, rows: usize, status: &str) -> String {
+        let [x, y, z, w] = self.registers();
+        let input: Vec<_> = self.remaining_input().map(|d| d.to_string()).collect();
+        let mut lines = vec![
+            format!("pc {}/{}  {}", self.pc(), self.program.len(), status),
+            format!("x {}  y {}  z {}  w {}", x, y, z, w),
+            format!("input [{}]", input.join(" ")),
+            String::new(),
+        ];
+
+        let height = rows.saturating_sub(lines.len() + 2).max(1);
+        let first = cursor.saturating_sub(height / 2);
+        for idx in first..(first + height).min(self.program.len()) {
+            let marker = match (idx == self.pc(), idx == cursor) {
+                (true, _) => '>',
+                (false, true) => '-',
+                _ => ' ',
+            };
+            let mut line = format!("{} {:>4}  {}", marker, idx, self.program[idx]);
+            if self.breakpoints.contains(&idx) {
+                line = format!(
+                    "{}{}{}",
+                    color::Fg(color::Red),
+                    line,
+                    color::Fg(color::Reset)
+                );
+            }
+            if idx == self.pc() {
+                line = format!("{}{}{}", style::Invert, line, style::Reset);
+            }
+            lines.push(line);
+        }
+
+        lines.push(String::new());
+        lines.push(String::from(
+            "s step  b back  n next block  p previous block  c continue  r reverse  \
+             j/k move  t breakpoint  q quit",
+        ));
+        lines.join("\r\n")
+    }
+}
+
+/// The program rejects every input.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NoModelNumber;
+
+impl fmt::Display for NoModelNumber {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "no model number is valid")
+    }
+}
+
+impl Error for NoModelNumber {}
+
+#[aoc(day24, part1)]
+pub fn part1(input: &[Instruction]) -> Result<u64, NoModelNumber> {
+    let number = find_model_number(input, true).ok_or(NoModelNumber)?;
+    assert!(is_valid(input, number));
+    Ok(number)
 }
 
 #[aoc(day24, part2)]
-fn part2(input: &[Instruction]) -> usize {
-    let mut alu = ALU::new();
-    let input_num = 11721151118175;
-    let input_str = input_num.to_string();
-    if !input_str.contains('0') {
-        alu.clear_input();
-        alu.execute_program(input, input_str);
-        if alu.z == 0 {
-            println!("FOUND IT");
-            return input_num;
+pub fn part2(input: &[Instruction]) -> Result<u64, NoModelNumber> {
+    let number = find_model_number(input, false).ok_or(NoModelNumber)?;
+    assert!(is_valid(input, number));
+    Ok(number)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::fs::read_to_string;
+
+    #[test]
+    fn test_part1() {
+        let input = read_to_string("input/2021/day24.txt").unwrap();
+        let input = load_input(&input).unwrap();
+        assert_eq!(part1(&input), Ok(53999995829399));
+    }
+
+    #[test]
+    fn test_part2() {
+        let input = read_to_string("input/2021/day24.txt").unwrap();
+        let input = load_input(&input).unwrap();
+        assert_eq!(part2(&input), Ok(11721151118175));
+    }
+
+    #[test]
+    fn test_small_programs() {
+        // Two digits that have to add up to 12
+        let input = load_input("inp w\ninp x\nadd z w\nadd z x\nadd z -12").unwrap();
+        assert_eq!(find_model_number(&input, true), Some(93));
+        assert_eq!(find_model_number(&input, false), Some(39));
+
+        // An odd digit can never be 0 mod 2
+        let input = load_input("inp w\nmod w 2\nadd z w\nmul z 5").unwrap();
+        assert_eq!(find_model_number(&input, true), Some(8));
+        let input = load_input("inp w\nmod w 2\neql w 0\nadd z w").unwrap();
+        assert_eq!(find_model_number(&input, false), Some(1));
+        let input = load_input("inp w\nadd z w").unwrap();
+        assert_eq!(find_model_number(&input, false), None);
+
+        // A 9 divides by 0, which rejects it rather than crashing
+        let input = load_input("inp w\nadd w -9\ndiv z w\nadd z 1").unwrap();
+        assert_eq!(find_model_number(&input, true), None);
+        let input = load_input("inp w\nadd w -9\ndiv z w\nadd z w\nadd z 1").unwrap();
+        assert_eq!(find_model_number(&input, true), Some(8));
+
+        // So does one that overflows
+        let input = load_input("inp w\nmul w 999999999999\nmul w 999999999999\nadd z w").unwrap();
+        assert_eq!(find_model_number(&input, true), None);
+        assert_eq!(part1(&input), Err(NoModelNumber));
+    }
+
+    #[test]
+    fn test_symbolic() {
+        let input = read_to_string("input/2021/day24.txt").unwrap();
+        let input = load_input(&input).unwrap();
+        let paths = digit_constraints(&input).unwrap();
+        assert_eq!(paths.len(), 1);
+        let mut rules: Vec<_> = paths[0].iter().map(|c| c.to_string()).collect();
+        rules.sort();
+        assert_eq!(
+            rules,
+            vec!["w10=w9+7", "w11=w2-6", "w12=w1+6", "w13=w0+4", "w5=w4", "w7=w6-4", "w8=w3-1"]
+        );
+        assert_eq!(
+            solve_constraints(&paths[0], input_count(&input)),
+            Some((53999995829399, 11721151118175))
+        );
+
+        let input = load_input("inp w\ninp x\nadd z w\nadd z x\nadd z -12").unwrap();
+        let paths = digit_constraints(&input).unwrap();
+        assert_eq!(paths[0][0].to_string(), "w0 + w1 - 12 = 0");
+        assert_eq!(solve_constraints(&paths[0], 2), None);
+    }
+
+    #[test]
+    fn test_disassemble() {
+        let text = read_to_string("input/2021/day24.txt").unwrap();
+        let input = load_input(&text).unwrap();
+        let rendered: Vec<_> = input.iter().map(|inst| inst.to_string()).collect();
+        assert_eq!(rendered, text.lines().collect::<Vec<_>>());
+
+        let blocks = recognize_monad(&input).unwrap();
+        assert_eq!(blocks.len(), 14);
+        assert_eq!(
+            blocks[0],
+            MonadBlock {
+                div: 1,
+                check: 15,
+                offset: 13
+            }
+        );
+        let mut pairs: Vec<_> = monad_pairs(&blocks)
+            .unwrap()
+            .into_iter()
+            .map(|(i, j, k)| format_pair(i, j, k))
+            .collect();
+        pairs.sort();
+        let mut symbolic: Vec<_> = digit_constraints(&input).unwrap()[0]
+            .iter()
+            .map(|c| c.to_string())
+            .collect();
+        symbolic.sort();
+        assert_eq!(pairs, symbolic);
+        let listing = monad_listing(&blocks);
+        assert_eq!(
+            listing.lines().nth(6),
+            Some(" 5    26    -11       6  | | | | pop  w5=w4")
+        );
+
+        let err = recognize_monad(&input[..20]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "block 1 line 2: expected add x z, found the end of the program"
+        );
+        let input = load_input("inp w\nmul x 1").unwrap();
+        let err = recognize_monad(&input).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "block 0 line 1: expected mul x 0, found mul x 1"
+        );
+    }
+
+    #[test]
+    fn test_debugger() {
+        let input = read_to_string("input/2021/day24.txt").unwrap();
+        let input = load_input(&input).unwrap();
+        let mut debugger = Debugger::new(&input, "53999995829399").unwrap();
+        debugger.next_block().unwrap();
+        assert_eq!(debugger.pc(), 18);
+        assert_eq!(debugger.registers()[2], 5 + 13);
+        assert_eq!(debugger.remaining_input().count(), 13);
+
+        debugger.toggle_breakpoint(100);
+        assert_eq!(debugger.resume(), Err(Stop::Breakpoint(100)));
+        let z = debugger.registers()[2];
+        assert_eq!(debugger.resume(), Err(Stop::End));
+        assert_eq!(debugger.registers()[2], 0);
+        assert_eq!(debugger.reverse(), Err(Stop::Breakpoint(100)));
+        assert_eq!(debugger.registers()[2], z);
+
+        debugger.previous_block().unwrap();
+        assert_eq!(debugger.pc(), 90);
+        debugger.step_back().unwrap();
+        debugger.previous_block().unwrap();
+        assert_eq!(debugger.pc(), 72);
+        assert_eq!(debugger.reverse(), Err(Stop::Start));
+        assert_eq!(debugger.registers(), [0; 4]);
+
+        let mut debugger = Debugger::new(&input, "5").unwrap();
+        debugger.next_block().unwrap();
+        assert_eq!(debugger.next_block(), Err(Stop::Fault("out of input")));
+        assert_eq!(debugger.pc(), 18);
+        assert!(debugger.render(18, 10, "").contains("input []"));
+        assert!(Debugger::new(&input, "12x").is_err());
+    }
+
+    #[test]
+    fn test_compile() {
+        let input = read_to_string("input/2021/day24.txt").unwrap();
+        let input = load_input(&input).unwrap();
+        let compiled = compile(&input);
+        assert_eq!(compiled.inputs(), 14);
+        assert!(compiled.len() < input.len() * 7 / 8, "{}", compiled.len());
+
+        let mut seed = 2021u64;
+        for _ in 0..200 {
+            let number: String = (0..14)
+                .map(|_| {
+                    seed ^= seed << 13;
+                    seed ^= seed >> 7;
+                    seed ^= seed << 17;
+                    char::from(b'1' + (seed % 9) as u8)
+                })
+                .collect();
+            let mut alu = ALU::new();
+            alu.execute_program(&input, number.clone());
+            let digits: Vec<_> = number.bytes().map(|b| (b - b'0') as i64).collect();
+            assert_eq!(compiled.run([0; 4], &digits), Some(alu.z));
         }
+
+        // Only w5=w4 and w7=w6-4 work with everything else fixed
+        let template = [5, 3, 9, 9, 9, 9, 9, 5, 8, 2, 9, 3, 9, 9];
+        let accepted = brute_force(&compiled, &template, &[4, 5, 6, 7]);
+        assert_eq!(accepted.len(), 9 * 5);
+        assert_eq!(accepted.last(), Some(&53999995829399));
+        assert!(accepted.contains(&53991151829399));
+
+        let input = load_input("inp w\ndiv w 0").unwrap();
+        assert_eq!(compile(&input).run([0; 4], &[1]), None);
+        // Nothing reads x, so only the inputs and z survive
+        let input = load_input("inp w\neql x w\neql x 0\ninp y\nadd z w\nmul z y").unwrap();
+        let compiled = compile(&input);
+        assert_eq!(compiled.len(), 4);
+        assert_eq!(compiled.run([0; 4], &[3, 4]), Some(12));
+        // Unless what's done to x might overflow
+        let input = load_input("inp w\nmul x w\nmul x w").unwrap();
+        let compiled = compile(&input);
+        assert_eq!(compiled.len(), 3);
+        assert_eq!(compiled.run([0, 0, 0, 0], &[9]), Some(0));
+        assert_eq!(compiled.run([i64::MAX, 0, 0, 0], &[9]), None);
     }
-    panic!("Shouldn't get here");
 }
@@ -1,103 +1,58 @@
-use std::collections::{HashSet, HashMap};
+use crate::error::ParseError;
+use crate::grid::{Edge, Grid};
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
-enum CType {
+pub enum Cucumber {
     East,
     South,
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
-struct Cucumber {
-    ctype: CType,
-    x: i32,
-    y: i32,
-}
-
-impl Cucumber {
-    fn new(ctype: CType, x: i32, y: i32) -> Cucumber {
-        Cucumber {
-            ctype,
-            x,
-            y,
-        }
-    }
-}
-
 #[aoc_generator(day25)]
-fn load_input(input: &str) -> HashMap<(i32, i32), Cucumber> {
-    let mut output = HashMap::new();
-    for (y, line) in input.lines().enumerate() {
-        for (x, c) in line.chars().enumerate() {
-            if c == 'v' {
-                let new = Cucumber::new(CType::South, x as i32, y as i32);
-                output.insert((x as i32, y as i32), new);
-            } else if c == '>' {
-                let new = Cucumber::new(CType::East, x as i32, y as i32);
-                output.insert((x as i32, y as i32), new);
-            }
-        }
-    }
-    output
+pub fn load_input(input: &str) -> Result<Grid<Option<Cucumber>>, ParseError> {
+    let grid = Grid::parse(input, "'>', 'v' or '.'", |c| match c {
+        '>' => Some(Some(Cucumber::East)),
+        'v' => Some(Some(Cucumber::South)),
+        '.' => Some(None),
+        _ => None,
+    })?;
+    Ok(grid.with_edge(Edge::Wrapping))
 }
 
-fn step(cucumbers: &mut HashMap<(i32, i32), Cucumber>) -> bool {
-    let width = 139;
-    let height = 137;
-
-    // First we move the East herd first
-    let mut east_moves = vec![];
-    for (key, c) in cucumbers.iter() {
-        if c.ctype == CType::East {
-            let nx = (c.x + 1) % width;
-            if cucumbers.contains_key(&(nx, c.y)) {
-                // Already a cucumber here, no movement
-            } else {
-                // Move it East
-                east_moves.push(key.clone());
-            }
-        }
-    }
-
-    for m in &east_moves {
-        let mut temp = cucumbers.remove(m).unwrap();
-        let nx = (temp.x + 1) % width;
-        temp.x = nx;
-        cucumbers.insert((nx, temp.y), temp);
-    }
-
-    // Next we move the South herd
-    let mut south_moves = vec![];
-    for (key, c) in cucumbers.iter() {
-        if c.ctype == CType::South {
-            let ny = (c.y + 1) % height;
-            if cucumbers.contains_key(&(c.x, ny)) {
-                // Already a cucumber here, no movement
-            } else {
-                // Move it South
-                south_moves.push(key.clone());
+fn step_herd(cucumbers: &mut Grid<Option<Cucumber>>, herd: Cucumber) -> usize {
+    let delta = match herd {
+        Cucumber::East => (1, 0),
+        Cucumber::South => (0, 1),
+    };
+
+    let mut moves = vec![];
+    for (point, c) in cucumbers.iter() {
+        if *c == Some(herd) {
+            let next = cucumbers.offset(point, delta).unwrap();
+            if cucumbers[next].is_none() {
+                moves.push((point, next));
             }
         }
     }
 
-    for m in &south_moves {
-        let mut temp = cucumbers.remove(m).unwrap();
-        let ny = (temp.y + 1) % height;
-        temp.y = ny;
-        cucumbers.insert((temp.x, ny), temp);
+    for (from, to) in &moves {
+        cucumbers[*from] = None;
+        cucumbers[*to] = Some(herd);
     }
+    moves.len()
+}
 
-    east_moves.len() + south_moves.len() == 0
+fn step(cucumbers: &mut Grid<Option<Cucumber>>) -> bool {
+    // First we move the East herd, then the South herd
+    let east_moves = step_herd(cucumbers, Cucumber::East);
+    let south_moves = step_herd(cucumbers, Cucumber::South);
+    east_moves + south_moves == 0
 }
 
 #[aoc(day25, part1)]
-fn part1(input: &HashMap<(i32, i32), Cucumber>) -> usize {
+pub fn part1(input: &Grid<Option<Cucumber>>) -> usize {
     let mut cmap = input.clone();
     let mut counter = 1;
     loop {
-        if counter % 1000 == 0 {
-            println!("counter: {}", counter);
-        }
-
         if step(&mut cmap) {
             return counter;
         }
@@ -107,7 +62,7 @@ fn part1(input: &HashMap<(i32, i32), Cucumber>) -> usize {
 }
 
 #[aoc(day25, part2)]
-fn part2(input: &HashMap<(i32, i32), Cucumber>) -> usize {
+pub fn part2(_input: &Grid<Option<Cucumber>>) -> usize {
     0
 }
 
@@ -119,14 +74,14 @@ mod test {
     #[test]
     fn test_part1() {
         let input = read_to_string("input/2021/25.txt").unwrap();
-        let input = load_input(&input);
+        let input = load_input(&input).unwrap();
         assert_eq!(part1(&input), 58);
     }
 
     #[test]
     fn test_part2() {
         let input = read_to_string("input/2021/25.txt").unwrap();
-        let input = load_input(&input);
+        let input = load_input(&input).unwrap();
         assert_eq!(part2(&input), 0);
     }
 }
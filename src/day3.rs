@@ -1,8 +1,28 @@
+use crate::error::ParseError;
 use std::cmp::Ordering;
 
 #[aoc_generator(day3)]
-pub fn load_input(input: &str) -> Vec<String> {
-    input.lines().map(|x| x.to_string()).collect()
+pub fn load_input(input: &str) -> Result<Vec<String>, ParseError> {
+    let mut output: Vec<String> = vec![];
+    for (idx, line) in input.lines().enumerate() {
+        for (col, c) in line.chars().enumerate() {
+            if c != '0' && c != '1' {
+                return Err(ParseError::invalid_char(idx, col, c, "a binary digit"));
+            }
+        }
+        if let Some(first) = output.first() {
+            if line.len() != first.len() {
+                return Err(ParseError::invalid(
+                    idx,
+                    line,
+                    line,
+                    "a number as wide as the first line",
+                ));
+            }
+        }
+        output.push(line.to_string());
+    }
+    Ok(output)
 }
 
 pub fn gamma_rate(input: &[String]) -> Vec<i64> {
@@ -32,7 +52,7 @@ pub fn gamma_rate(input: &[String]) -> Vec<i64> {
 
 #[aoc(day3, part1)]
 pub fn part1(input: &[String]) -> i64 {
-    let gamma = gamma_rate(&input.to_vec());
+    let gamma = gamma_rate(input);
     let epsilon: Vec<_> = gamma.iter().map(|x| x ^ 1).collect();
     to_int(&gamma) * to_int(&epsilon)
 }
@@ -105,14 +125,14 @@ mod test {
     #[test]
     fn test_part1() {
         let input = read_to_string("input/2021/03.txt").unwrap();
-        let input = load_input(&input);
+        let input = load_input(&input).unwrap();
         assert_eq!(part1(&input), 198);
     }
 
     #[test]
     fn test_part2() {
         let input = read_to_string("input/2021/03.txt").unwrap();
-        let input = load_input(&input);
+        let input = load_input(&input).unwrap();
         assert_eq!(part2(&input), 230);
     }
 }
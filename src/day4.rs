@@ -1,3 +1,5 @@
+use crate::error::{parse_token, ParseError};
+
 #[derive(Copy, Clone, Debug)]
 pub struct Board {
     pub rows: [[u8; 5]; 5],
@@ -56,25 +58,34 @@ impl Board {
 }
 
 #[aoc_generator(day4)]
-pub fn load_input(input: &str) -> (Vec<u8>, Vec<Board>) {
-    let inputs = input.lines().next().unwrap();
-    let inputs: Vec<_> = inputs
-        .split(',')
-        .map(|x| x.parse::<u8>().unwrap())
-        .collect();
+pub fn load_input(input: &str) -> Result<(Vec<u8>, Vec<Board>), ParseError> {
+    let first = input
+        .lines()
+        .next()
+        .ok_or_else(|| ParseError::eof(0, "the numbers drawn"))?;
+    let mut inputs = vec![];
+    for value in first.split(',') {
+        inputs.push(parse_token::<u8>(0, first, Some(value), "a number")?);
+    }
 
     let markers = [[0u8; 5]; 5];
     let mut boards = vec![];
     let mut rows = [[0u8; 5]; 5];
     let mut rcntr = 0;
-    for line in input.lines().skip(2) {
+    let mut last_idx = 0;
+    for (idx, line) in input.lines().enumerate().skip(2) {
+        last_idx = idx;
         if !line.is_empty() {
             let mut cntr = 0;
-            for value in line.split(' ') {
-                if let Ok(num) = value.parse::<u8>() {
-                    rows[rcntr][cntr] = num;
-                    cntr += 1;
+            for value in line.split(' ').filter(|x| !x.is_empty()) {
+                if cntr == 5 {
+                    return Err(ParseError::invalid(idx, line, value, "5 numbers per row"));
                 }
+                rows[rcntr][cntr] = parse_token(idx, line, Some(value), "a number")?;
+                cntr += 1;
+            }
+            if cntr < 5 {
+                return Err(ParseError::missing(idx, line, "5 numbers per row"));
             }
             rcntr += 1;
 
@@ -82,10 +93,15 @@ pub fn load_input(input: &str) -> (Vec<u8>, Vec<Board>) {
                 boards.push(Board { rows, markers });
                 rcntr = 0;
             }
+        } else if rcntr != 0 {
+            return Err(ParseError::missing(idx, line, "5 rows per board"));
         }
     }
+    if rcntr != 0 {
+        return Err(ParseError::eof(last_idx + 1, "5 rows per board"));
+    }
 
-    (inputs, boards)
+    Ok((inputs, boards))
 }
 
 #[aoc(day4, part1)]
@@ -145,14 +161,14 @@ mod test {
     #[test]
     fn test_part1() {
         let input = read_to_string("input/2021/04.txt").unwrap();
-        let input = load_input(&input);
+        let input = load_input(&input).unwrap();
         assert_eq!(part1(&input), 4512);
     }
 
     #[test]
     fn test_part2() {
         let input = read_to_string("input/2021/04.txt").unwrap();
-        let input = load_input(&input);
+        let input = load_input(&input).unwrap();
         assert_eq!(part2(&input), 1924);
     }
 }
@@ -1,3 +1,6 @@
+use crate::error::{parse_token, ParseError};
+use crate::grid::Grid;
+
 #[derive(Debug, Clone, Copy)]
 pub struct Segment {
     pub p1: (usize, usize),
@@ -5,15 +8,14 @@ pub struct Segment {
 }
 
 impl Segment {
-    #[allow(clippy::needless_range_loop)]
-    pub fn fill(&self, board: &mut Vec<Vec<usize>>, part2: bool) {
+    pub fn fill(&self, board: &mut Grid<usize>, part2: bool) {
         if self.p1.0 == self.p2.0 {
             // Horizontal line
             let x = self.p1.0;
             let starty = self.p1.1.min(self.p2.1);
             let stopy = self.p1.1.max(self.p2.1);
             for y in starty..(stopy + 1) {
-                board[x][y] += 1;
+                board[(x, y)] += 1;
             }
         } else if self.p1.1 == self.p2.1 {
             // Vertical line
@@ -21,7 +23,7 @@ impl Segment {
             let startx = self.p1.0.min(self.p2.0);
             let stopx = self.p1.0.max(self.p2.0);
             for x in startx..(stopx + 1) {
-                board[x][y] += 1;
+                board[(x, y)] += 1;
             }
         } else if part2 {
             // Diagonal
@@ -41,49 +43,46 @@ impl Segment {
                 if stopy < starty {
                     y = starty - i;
                 }
-                board[x][y] += 1;
+                board[(x, y)] += 1;
             }
         }
     }
 }
 
+fn parse_point(idx: usize, line: &str, point: Option<&str>) -> Result<(usize, usize), ParseError> {
+    let point = point.ok_or_else(|| ParseError::missing(idx, line, "a point"))?;
+    let mut coords = point.split(',');
+    let x = parse_token(idx, line, coords.next(), "an x coordinate")?;
+    let y = parse_token(idx, line, coords.next(), "a y coordinate")?;
+    Ok((x, y))
+}
+
 #[aoc_generator(day5)]
-pub fn load_input(input: &str) -> Vec<Segment> {
+pub fn load_input(input: &str) -> Result<Vec<Segment>, ParseError> {
     let mut output = vec![];
-    for line in input.lines() {
+    for (idx, line) in input.lines().enumerate() {
         let mut _line = line.split(" -> ");
-        let mut v1 = _line.next().unwrap().split(',');
-        let x1 = v1.next().unwrap().parse::<usize>().unwrap();
-        let y1 = v1.next().unwrap().parse::<usize>().unwrap();
-
-        let mut v2 = _line.next().unwrap().split(',');
-        let x2 = v2.next().unwrap().parse::<usize>().unwrap();
-        let y2 = v2.next().unwrap().parse::<usize>().unwrap();
-
-        output.push(Segment {
-            p1: (x1, y1),
-            p2: (x2, y2),
-        });
+        let p1 = parse_point(idx, line, _line.next())?;
+        let p2 = parse_point(idx, line, _line.next())?;
+        output.push(Segment { p1, p2 });
     }
-    output
+    Ok(output)
 }
 
-#[allow(clippy::needless_range_loop)]
-pub fn count_board(board: &[Vec<usize>]) -> usize {
-    let mut total = 0;
-    for x in 0..1000 {
-        for y in 0..1000 {
-            if board[x][y] >= 2 {
-                total += 1;
-            }
-        }
-    }
-    total
+// Just big enough to hold every segment
+pub fn new_board(segments: &[Segment]) -> Grid<usize> {
+    let width = segments.iter().map(|s| s.p1.0.max(s.p2.0) + 1).max();
+    let height = segments.iter().map(|s| s.p1.1.max(s.p2.1) + 1).max();
+    Grid::new(width.unwrap_or(0), height.unwrap_or(0), 0)
+}
+
+pub fn count_board(board: &Grid<usize>) -> usize {
+    board.values().filter(|&&v| v >= 2).count()
 }
 
 #[aoc(day5, part1)]
 pub fn part1(input: &[Segment]) -> usize {
-    let mut board = vec![vec![0_usize; 1000]; 1000];
+    let mut board = new_board(input);
     for segment in input {
         segment.fill(&mut board, false);
     }
@@ -92,7 +91,7 @@ pub fn part1(input: &[Segment]) -> usize {
 
 #[aoc(day5, part2)]
 pub fn part2(input: &[Segment]) -> usize {
-    let mut board = vec![vec![0_usize; 1000]; 1000];
+    let mut board = new_board(input);
     for segment in input {
         segment.fill(&mut board, true);
     }
@@ -107,14 +106,14 @@ mod test {
     #[test]
     fn test_part1() {
         let input = read_to_string("input/2021/05.txt").unwrap();
-        let input = load_input(&input);
+        let input = load_input(&input).unwrap();
         assert_eq!(part1(&input), 5);
     }
 
     #[test]
     fn test_part2() {
         let input = read_to_string("input/2021/05.txt").unwrap();
-        let input = load_input(&input);
+        let input = load_input(&input).unwrap();
         assert_eq!(part2(&input), 12);
     }
 }
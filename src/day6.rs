@@ -1,3 +1,4 @@
+use crate::error::{parse_token, ParseError};
 use std::collections::HashMap;
 
 #[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
@@ -42,16 +43,28 @@ impl Fish {
 }
 
 #[aoc_generator(day6)]
-pub fn load_input(input: &str) -> Vec<Fish> {
-    let input = input.lines().next().unwrap();
+pub fn load_input(input: &str) -> Result<Vec<Fish>, ParseError> {
+    let input = input
+        .lines()
+        .next()
+        .ok_or_else(|| ParseError::eof(0, "a list of timers"))?;
     let mut output = vec![];
     for fish in input.split(',') {
+        let timer = parse_token(0, input, Some(fish), "a timer")?;
+        if timer > 8 {
+            return Err(ParseError::invalid(
+                0,
+                input,
+                fish.trim(),
+                "a timer from 0 to 8",
+            ));
+        }
         output.push(Fish {
-            timer: fish.parse::<usize>().unwrap(),
+            timer,
             day_spawned: 0,
         });
     }
-    output
+    Ok(output)
 }
 
 #[aoc(day6, part1)]
@@ -106,14 +119,14 @@ mod test {
     #[test]
     fn test_part1() {
         let input = read_to_string("input/2021/06.txt").unwrap();
-        let input = load_input(&input);
+        let input = load_input(&input).unwrap();
         assert_eq!(part1(&input), 5934);
     }
 
     #[test]
     fn test_part2() {
         let input = read_to_string("input/2021/06.txt").unwrap();
-        let input = load_input(&input);
+        let input = load_input(&input).unwrap();
         assert_eq!(part2(&input), 26984457539);
     }
 }
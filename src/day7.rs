@@ -1,16 +1,21 @@
+use crate::error::{parse_token, ParseError};
+
 #[aoc_generator(day7)]
-pub fn load_input(input: &str) -> Vec<i64> {
-    let input = input.lines().next().unwrap();
+pub fn load_input(input: &str) -> Result<Vec<i64>, ParseError> {
+    let input = input
+        .lines()
+        .next()
+        .ok_or_else(|| ParseError::eof(0, "a list of positions"))?;
     let mut output = vec![];
     for num in input.split(',') {
-        output.push(num.parse::<i64>().unwrap());
+        output.push(parse_token(0, input, Some(num), "a position")?);
     }
-    output
+    Ok(output)
 }
 
 #[aoc(day7, part1)]
 pub fn part1(input: &[i64]) -> i64 {
-    let n = *input.iter().max().unwrap() as i64;
+    let n = *input.iter().max().unwrap();
     let mut cost_arr = vec![];
     for col in 0..n {
         let mut cost = 0;
@@ -24,12 +29,12 @@ pub fn part1(input: &[i64]) -> i64 {
 
 #[aoc(day7, part2)]
 pub fn part2(input: &[i64]) -> i64 {
-    let n = *input.iter().max().unwrap() as i64;
+    let n = *input.iter().max().unwrap();
     let mut cost_arr = vec![];
     for col in 0..n {
         let mut cost = 0;
         for value in input {
-            let dist = (col - *value as i64).abs();
+            let dist = (col - *value).abs();
             cost += dist * (dist + 1) / 2;
         }
         cost_arr.push(cost);
@@ -45,14 +50,14 @@ mod test {
     #[test]
     fn test_part1() {
         let input = read_to_string("input/2021/07.txt").unwrap();
-        let input = load_input(&input);
+        let input = load_input(&input).unwrap();
         assert_eq!(part1(&input), 37);
     }
 
     #[test]
     fn test_part2() {
         let input = read_to_string("input/2021/07.txt").unwrap();
-        let input = load_input(&input);
+        let input = load_input(&input).unwrap();
         assert_eq!(part2(&input), 168);
     }
 }
@@ -1,29 +1,32 @@
+use crate::error::{next_token, ParseError};
 use rayon::prelude::*;
 use std::collections::{HashMap, HashSet};
 
-#[allow(clippy::comparison_to_empty)]
+fn parse_words(idx: usize, line: &str, part: &str) -> Result<Vec<String>, ParseError> {
+    let mut words = vec![];
+    for word in part.split(' ').filter(|x| !x.is_empty()) {
+        if !word.chars().all(|c| ('a'..='g').contains(&c)) {
+            return Err(ParseError::invalid(idx, line, word, "segments a through g"));
+        }
+        words.push(String::from(word));
+    }
+    Ok(words)
+}
+
+#[allow(clippy::type_complexity)]
 #[aoc_generator(day8)]
-pub fn load_input(input: &str) -> Vec<(Vec<String>, Vec<String>)> {
+pub fn load_input(input: &str) -> Result<Vec<(Vec<String>, Vec<String>)>, ParseError> {
     let mut output = vec![];
-    for line in input.lines() {
+    for (idx, line) in input.lines().enumerate() {
         let mut asdf = line.split('|');
-        let signals: Vec<_> = asdf
-            .next()
-            .unwrap()
-            .split(' ')
-            .filter(|x| *x != "")
-            .map(String::from)
-            .collect();
-        let output_values: Vec<_> = asdf
-            .next()
-            .unwrap()
-            .split(' ')
-            .filter(|x| *x != "")
-            .map(String::from)
-            .collect();
+        let signals = parse_words(idx, line, next_token(idx, line, &mut asdf, "signals")?)?;
+        let output_values = parse_words(idx, line, next_token(idx, line, &mut asdf, "'|'")?)?;
+        if signals.len() != 10 {
+            return Err(ParseError::invalid(idx, line, line, "10 signal patterns"));
+        }
         output.push((signals, output_values));
     }
-    output
+    Ok(output)
 }
 
 #[aoc(day8, part1)]
@@ -162,14 +165,14 @@ mod test {
     #[test]
     fn test_part1() {
         let input = read_to_string("input/2021/08.txt").unwrap();
-        let input = load_input(&input);
+        let input = load_input(&input).unwrap();
         assert_eq!(part1(&input), 26);
     }
 
     #[test]
     fn test_part2() {
         let input = read_to_string("input/2021/08.txt").unwrap();
-        let input = load_input(&input);
+        let input = load_input(&input).unwrap();
         assert_eq!(part2(&input), 61229);
     }
 }
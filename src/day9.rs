@@ -1,84 +1,38 @@
+use crate::error::ParseError;
+use crate::grid::Grid;
 use std::collections::HashSet;
 
 #[aoc_generator(day9)]
-pub fn load_input(input: &str) -> Vec<Vec<u32>> {
-    let mut output = vec![];
-    for line in input.lines() {
-        let mut temp = vec![];
-        for value in line.chars() {
-            let num = value.to_digit(10).unwrap();
-            temp.push(num);
-        }
-        output.push(temp);
-    }
-    output
+pub fn load_input(input: &str) -> Result<Grid<u32>, ParseError> {
+    Grid::parse_digits(input)
 }
 
-fn is_low(map: &[Vec<u32>], x: usize, y: usize) -> bool {
-    let check = map[y][x];
-
-    if x > 0 && map[y][x - 1] <= check {
-        return false;
-    }
-
-    if x < map[0].len() - 1 && map[y][x + 1] <= check {
-        return false;
-    }
-
-    if y > 0 && map[y - 1][x] <= check {
-        return false;
-    }
-
-    if y < map.len() - 1 && map[y + 1][x] <= check {
-        return false;
-    }
-
-    true
+fn is_low(map: &Grid<u32>, point: (usize, usize)) -> bool {
+    let check = map[point];
+    map.neighbors4(point).all(|n| map[n] > check)
 }
 
-fn get_neighbors(map: &[Vec<u32>], point: &(usize, usize)) -> Vec<(usize, usize)> {
-    let mut output = vec![];
-    let x = point.0;
-    let y = point.1;
-    if x > 0 && map[y][x - 1] != 9 {
-        output.push((x - 1, y));
-    }
-
-    if x < map[0].len() - 1 && map[y][x + 1] != 9 {
-        output.push((x + 1, y));
-    }
-
-    if y > 0 && map[y - 1][x] != 9 {
-        output.push((x, y - 1));
-    }
-
-    if y < map.len() - 1 && map[y + 1][x] != 9 {
-        output.push((x, y + 1));
-    }
-    output
+fn get_neighbors(map: &Grid<u32>, point: &(usize, usize)) -> Vec<(usize, usize)> {
+    map.neighbors4(*point).filter(|&n| map[n] != 9).collect()
 }
 
 #[aoc(day9, part1)]
-pub fn part1(input: &[Vec<u32>]) -> i64 {
+pub fn part1(input: &Grid<u32>) -> i64 {
     let mut output = 0;
-    for (y, line) in input.iter().enumerate() {
-        for (x, point) in line.iter().enumerate() {
-            if is_low(input, x, y) {
-                output += point + 1;
-            }
+    for (point, height) in input.iter() {
+        if is_low(input, point) {
+            output += height + 1;
         }
     }
     output as i64
 }
 
 #[aoc(day9, part2)]
-pub fn part2(input: &[Vec<u32>]) -> i64 {
+pub fn part2(input: &Grid<u32>) -> i64 {
     let mut low_points = vec![];
-    for (y, line) in input.iter().enumerate() {
-        for (x, _point) in line.iter().enumerate() {
-            if is_low(input, x, y) {
-                low_points.push((x, y));
-            }
+    for point in input.positions() {
+        if is_low(input, point) {
+            low_points.push(point);
         }
     }
 
@@ -129,14 +83,14 @@ mod test {
     #[test]
     fn test_part1() {
         let input = read_to_string("input/2021/09.txt").unwrap();
-        let input = load_input(&input);
+        let input = load_input(&input).unwrap();
         assert_eq!(part1(&input), 15);
     }
 
     #[test]
     fn test_part2() {
         let input = read_to_string("input/2021/09.txt").unwrap();
-        let input = load_input(&input);
+        let input = load_input(&input).unwrap();
         assert_eq!(part2(&input), 1134);
     }
 }
@@ -0,0 +1,203 @@
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+/// Error returned by the `load_input` generators when the puzzle input is
+/// malformed. Line and column numbers are 1-based so they line up with what an
+/// editor shows.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseError {
+    /// A token was found but isn't what we expected at that position.
+    InvalidToken {
+        line: usize,
+        column: usize,
+        token: String,
+        expected: &'static str,
+    },
+    /// The line ended before we found the token we expected.
+    MissingToken {
+        line: usize,
+        column: usize,
+        expected: &'static str,
+    },
+    /// The input ended before we found the line we expected.
+    UnexpectedEof { line: usize, expected: &'static str },
+}
+
+impl ParseError {
+    /// `token` must be a subslice of `line`, its column is worked out from
+    /// where it sits in the line. `idx` is the 0-based line index as handed
+    /// out by `enumerate()`.
+    pub fn invalid(idx: usize, line: &str, token: &str, expected: &'static str) -> ParseError {
+        ParseError::InvalidToken {
+            line: idx + 1,
+            column: column_of(line, token),
+            token: String::from(token),
+            expected,
+        }
+    }
+
+    /// Same as `invalid`, but for a single character at 0-based `col`.
+    pub fn invalid_char(idx: usize, col: usize, c: char, expected: &'static str) -> ParseError {
+        ParseError::InvalidToken {
+            line: idx + 1,
+            column: col + 1,
+            token: c.to_string(),
+            expected,
+        }
+    }
+
+    /// Points just past the end of `line`.
+    pub fn missing(idx: usize, line: &str, expected: &'static str) -> ParseError {
+        ParseError::MissingToken {
+            line: idx + 1,
+            column: line.chars().count() + 1,
+            expected,
+        }
+    }
+
+    pub fn eof(idx: usize, expected: &'static str) -> ParseError {
+        ParseError::UnexpectedEof {
+            line: idx + 1,
+            expected,
+        }
+    }
+
+    /// Move the error `lines` further down, for errors from parsing a section
+    /// that doesn't start at the top of the input.
+    pub fn shift(self, lines: usize) -> ParseError {
+        match self {
+            ParseError::InvalidToken {
+                line,
+                column,
+                token,
+                expected,
+            } => ParseError::InvalidToken {
+                line: line + lines,
+                column,
+                token,
+                expected,
+            },
+            ParseError::MissingToken {
+                line,
+                column,
+                expected,
+            } => ParseError::MissingToken {
+                line: line + lines,
+                column,
+                expected,
+            },
+            ParseError::UnexpectedEof { line, expected } => ParseError::UnexpectedEof {
+                line: line + lines,
+                expected,
+            },
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::InvalidToken {
+                line,
+                column,
+                token,
+                expected,
+            } => write!(
+                f,
+                "{}:{}: expected {}, found {:?}",
+                line, column, expected, token
+            ),
+            ParseError::MissingToken {
+                line,
+                column,
+                expected,
+            } => write!(
+                f,
+                "{}:{}: expected {}, found end of line",
+                line, column, expected
+            ),
+            ParseError::UnexpectedEof { line, expected } => {
+                write!(f, "{}: expected {}, found end of input", line, expected)
+            }
+        }
+    }
+}
+
+impl Error for ParseError {}
+
+/// 1-based column of `token` within `line`. Falls back to 1 if `token` isn't
+/// actually a piece of `line`.
+pub fn column_of(line: &str, token: &str) -> usize {
+    let start = line.as_ptr() as usize;
+    let offset = (token.as_ptr() as usize).wrapping_sub(start);
+    if offset <= line.len() && line.is_char_boundary(offset) {
+        line[..offset].chars().count() + 1
+    } else {
+        1
+    }
+}
+
+/// Parse `token` out of `line`, reporting a missing or unparseable token with
+/// its position.
+pub fn parse_token<T: FromStr>(
+    idx: usize,
+    line: &str,
+    token: Option<&str>,
+    expected: &'static str,
+) -> Result<T, ParseError> {
+    let token = token.ok_or_else(|| ParseError::missing(idx, line, expected))?;
+    let trimmed = token.trim();
+    trimmed
+        .parse::<T>()
+        .map_err(|_| ParseError::invalid(idx, line, trimmed, expected))
+}
+
+/// Pull the next token out of `iter`, reporting a missing one with its
+/// position.
+pub fn next_token<'a>(
+    idx: usize,
+    line: &str,
+    iter: &mut impl Iterator<Item = &'a str>,
+    expected: &'static str,
+) -> Result<&'a str, ParseError> {
+    iter.next()
+        .ok_or_else(|| ParseError::missing(idx, line, expected))
+}
+
+/// Parse one character of a digit map.
+pub fn parse_digit(idx: usize, col: usize, c: char, radix: u32) -> Result<u32, ParseError> {
+    c.to_digit(radix)
+        .ok_or_else(|| ParseError::invalid_char(idx, col, c, "a digit"))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_positions() {
+        let line = "forward 5x";
+        let token = line.split(' ').nth(1);
+        let err = parse_token::<i64>(3, line, token, "a number").unwrap_err();
+        assert_eq!(
+            err,
+            ParseError::InvalidToken {
+                line: 4,
+                column: 9,
+                token: String::from("5x"),
+                expected: "a number",
+            }
+        );
+        assert_eq!(err.to_string(), "4:9: expected a number, found \"5x\"");
+
+        let err = parse_token::<i64>(0, line, None, "a number").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "1:11: expected a number, found end of line"
+        );
+
+        let err = parse_digit(1, 2, 'x', 10).unwrap_err();
+        assert_eq!(err.to_string(), "2:3: expected a digit, found \"x\"");
+    }
+}
@@ -0,0 +1,289 @@
+use crate::error::ParseError;
+use std::ops::{Index, IndexMut};
+
+/// Offsets to the up, left, right and down neighbours.
+pub const NEIGHBORS4: [(i64, i64); 4] = [(0, -1), (-1, 0), (1, 0), (0, 1)];
+
+/// Offsets to all eight surrounding cells, row by row.
+pub const NEIGHBORS8: [(i64, i64); 8] = [
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (-1, 0),
+    (1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+];
+
+/// What lies past the edges of a grid.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Edge<T> {
+    /// Nothing, the grid just stops.
+    Bounded,
+    /// Walking off one edge comes back in on the opposite one.
+    Wrapping,
+    /// The grid goes on forever and every cell outside the stored ones
+    /// holds this value.
+    Infinite(T),
+}
+
+/// A dense 2D grid indexed by `(x, y)`, with `(0, 0)` in the top left corner.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+    edge: Edge<T>,
+}
+
+impl<T> Grid<T> {
+    pub fn from_fn(width: usize, height: usize, mut f: impl FnMut(usize, usize) -> T) -> Grid<T> {
+        let mut cells = Vec::with_capacity(width * height);
+        for y in 0..height {
+            for x in 0..width {
+                cells.push(f(x, y));
+            }
+        }
+        Grid {
+            width,
+            height,
+            cells,
+            edge: Edge::Bounded,
+        }
+    }
+
+    /// Parse a character map, one row per line. `f` turns a character into a
+    /// cell, or `None` if it isn't one of the `expected` characters.
+    pub fn parse(
+        input: &str,
+        expected: &'static str,
+        mut f: impl FnMut(char) -> Option<T>,
+    ) -> Result<Grid<T>, ParseError> {
+        let mut cells = vec![];
+        let mut width = 0;
+        let mut height = 0;
+        for (idx, line) in input.lines().enumerate() {
+            let mut row = 0;
+            for (col, c) in line.chars().enumerate() {
+                cells.push(f(c).ok_or_else(|| ParseError::invalid_char(idx, col, c, expected))?);
+                row += 1;
+            }
+            if idx == 0 {
+                width = row;
+            } else if row != width {
+                return Err(ParseError::invalid(idx, line, line, "rows of equal length"));
+            }
+            height += 1;
+        }
+        if width == 0 {
+            return Err(ParseError::eof(height, "a grid"));
+        }
+        Ok(Grid {
+            width,
+            height,
+            cells,
+            edge: Edge::Bounded,
+        })
+    }
+
+    pub fn with_edge(mut self, edge: Edge<T>) -> Grid<T> {
+        self.edge = edge;
+        self
+    }
+
+    pub fn edge(&self) -> &Edge<T> {
+        &self.edge
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn contains(&self, x: i64, y: i64) -> bool {
+        x >= 0 && y >= 0 && (x as usize) < self.width && (y as usize) < self.height
+    }
+
+    /// Look up any point, inside the grid or not, following the edge rules.
+    pub fn get(&self, x: i64, y: i64) -> Option<&T> {
+        if self.contains(x, y) {
+            return Some(&self[(x as usize, y as usize)]);
+        }
+        match &self.edge {
+            Edge::Bounded => None,
+            Edge::Wrapping => {
+                let x = x.rem_euclid(self.width as i64) as usize;
+                let y = y.rem_euclid(self.height as i64) as usize;
+                Some(&self[(x, y)])
+            }
+            Edge::Infinite(outside) => Some(outside),
+        }
+    }
+
+    /// The stored cell `delta` away from `point`, if there is one. Wraps
+    /// around in `Wrapping` mode, otherwise stops at the edges.
+    pub fn offset(&self, point: (usize, usize), delta: (i64, i64)) -> Option<(usize, usize)> {
+        let x = point.0 as i64 + delta.0;
+        let y = point.1 as i64 + delta.1;
+        if self.contains(x, y) {
+            Some((x as usize, y as usize))
+        } else if let Edge::Wrapping = self.edge {
+            Some((
+                x.rem_euclid(self.width as i64) as usize,
+                y.rem_euclid(self.height as i64) as usize,
+            ))
+        } else {
+            None
+        }
+    }
+
+    pub fn neighbors4(&self, point: (usize, usize)) -> impl Iterator<Item = (usize, usize)> + '_ {
+        NEIGHBORS4
+            .iter()
+            .filter_map(move |&delta| self.offset(point, delta))
+    }
+
+    pub fn neighbors8(&self, point: (usize, usize)) -> impl Iterator<Item = (usize, usize)> + '_ {
+        NEIGHBORS8
+            .iter()
+            .filter_map(move |&delta| self.offset(point, delta))
+    }
+
+    /// Every point of the grid, row by row.
+    pub fn positions(&self) -> impl Iterator<Item = (usize, usize)> {
+        let width = self.width;
+        (0..self.height).flat_map(move |y| (0..width).map(move |x| (x, y)))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = ((usize, usize), &T)> {
+        self.positions().zip(self.cells.iter())
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &T> {
+        self.cells.iter()
+    }
+
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut T> {
+        self.cells.iter_mut()
+    }
+
+    pub fn map<U>(&self, mut f: impl FnMut(&T) -> U) -> Grid<U> {
+        let edge = match &self.edge {
+            Edge::Bounded => Edge::Bounded,
+            Edge::Wrapping => Edge::Wrapping,
+            Edge::Infinite(outside) => Edge::Infinite(f(outside)),
+        };
+        Grid {
+            width: self.width,
+            height: self.height,
+            cells: self.cells.iter().map(f).collect(),
+            edge,
+        }
+    }
+
+    /// The inverse of `parse`, one line per row.
+    pub fn render(&self, mut f: impl FnMut(&T) -> char) -> String {
+        let mut out = String::with_capacity((self.width + 1) * self.height);
+        for row in self.cells.chunks(self.width.max(1)) {
+            if !out.is_empty() {
+                out.push('\n');
+            }
+            out.extend(row.iter().map(&mut f));
+        }
+        out
+    }
+}
+
+impl<T: Clone> Grid<T> {
+    pub fn new(width: usize, height: usize, value: T) -> Grid<T> {
+        Grid {
+            width,
+            height,
+            cells: vec![value; width * height],
+            edge: Edge::Bounded,
+        }
+    }
+}
+
+impl Grid<u32> {
+    /// Parse a map of single decimal digits.
+    pub fn parse_digits(input: &str) -> Result<Grid<u32>, ParseError> {
+        Grid::parse(input, "a digit", |c| c.to_digit(10))
+    }
+}
+
+impl<T> Index<(usize, usize)> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, (x, y): (usize, usize)) -> &T {
+        assert!(
+            x < self.width && y < self.height,
+            "({}, {}) is outside the grid",
+            x,
+            y
+        );
+        &self.cells[y * self.width + x]
+    }
+}
+
+impl<T> IndexMut<(usize, usize)> for Grid<T> {
+    fn index_mut(&mut self, (x, y): (usize, usize)) -> &mut T {
+        assert!(
+            x < self.width && y < self.height,
+            "({}, {}) is outside the grid",
+            x,
+            y
+        );
+        &mut self.cells[y * self.width + x]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let map = "#..\n.#.\n..#";
+        let grid = Grid::parse(map, "'.' or '#'", |c| match c {
+            '#' => Some(true),
+            '.' => Some(false),
+            _ => None,
+        })
+        .unwrap();
+        assert_eq!((grid.width(), grid.height()), (3, 3));
+        assert!(grid[(2, 2)] && !grid[(1, 2)]);
+        assert_eq!(grid.render(|&b| if b { '#' } else { '.' }), map);
+
+        let err = Grid::parse_digits("123\n45").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "2:1: expected rows of equal length, found \"45\""
+        );
+        let err = Grid::parse_digits("123\n4x6").unwrap_err();
+        assert_eq!(err.to_string(), "2:2: expected a digit, found \"x\"");
+    }
+
+    #[test]
+    fn test_edges() {
+        let grid = Grid::parse_digits("123\n456\n789").unwrap();
+        let corner: Vec<_> = grid.neighbors4((0, 0)).collect();
+        assert_eq!(corner, vec![(1, 0), (0, 1)]);
+        assert_eq!(grid.neighbors8((1, 1)).count(), 8);
+        assert_eq!(grid.get(-1, 0), None);
+
+        let grid = grid.with_edge(Edge::Wrapping);
+        let corner: Vec<_> = grid.neighbors4((0, 0)).collect();
+        assert_eq!(corner, vec![(0, 2), (2, 0), (1, 0), (0, 1)]);
+        assert_eq!(grid.get(-1, -1), Some(&9));
+
+        let grid = grid.with_edge(Edge::Infinite(0));
+        assert_eq!(grid.neighbors8((0, 0)).count(), 3);
+        assert_eq!(grid.get(100, -5), Some(&0));
+        assert_eq!(grid.get(2, 1), Some(&6));
+    }
+}
@@ -3,6 +3,7 @@ extern crate aoc_runner;
 #[macro_use]
 extern crate aoc_runner_derive;
 
+pub mod bits;
 pub mod day1;
 pub mod day10;
 pub mod day11;
@@ -28,5 +29,11 @@ pub mod day6;
 pub mod day7;
 pub mod day8;
 pub mod day9;
+pub mod error;
+pub mod grid;
+pub mod search;
+pub mod solution;
+pub mod timing;
+pub mod verify;
 
 aoc_lib! { year = 2021 }
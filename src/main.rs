@@ -1,3 +1,844 @@
-use aoc_runner_derive::aoc_main;
+use aoc2021::solution::{self, Answer, SOLUTIONS};
+use aoc2021::timing::{self, SortKey};
+use aoc2021::verify::{self, Answers, Status};
+use aoc2021::{bits, day15, day23, day24};
+use clap::{value_parser, Arg, ArgAction, ArgMatches, Command};
+use std::error::Error;
+use std::fs::read_to_string;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+use termion::event::Key;
+use termion::input::TermRead;
+use termion::raw::IntoRawMode;
+use termion::screen::IntoAlternateScreen;
+use termion::{clear, cursor};
 
-aoc_main! { lib = aoc2021 }
+const DEFAULT_INPUT_DIR: &str = "input/2021";
+const DEFAULT_PATTERN: &str = "day{day}.txt";
+const DEFAULT_ANSWERS: &str = "answers.toml";
+
+/// Parse `input` with the day's generator and run each of `parts` on it.
+fn solve(day: u32, parts: &[u32], input: &str) -> Result<Vec<Answer>, Box<dyn Error>> {
+    let solution = solution::get(day).ok_or_else(|| format!("no solution for day {}", day))?;
+    let parsed = solution.parse(input)?;
+    parts
+        .iter()
+        .map(|&part| solution.part(part, &parsed))
+        .collect()
+}
+
+fn input_dir_args() -> [Arg; 2] {
+    [
+        Arg::new("input-dir")
+            .long("input-dir")
+            .value_parser(value_parser!(PathBuf))
+            .default_value(DEFAULT_INPUT_DIR)
+            .help("Directory holding the puzzle inputs"),
+        Arg::new("pattern")
+            .long("pattern")
+            .default_value(DEFAULT_PATTERN)
+            .help("Input file name within the directory, {day} is replaced by the day number"),
+    ]
+}
+
+fn cli() -> Command {
+    Command::new("aoc2021")
+        .about("Advent of Code 2021 solutions")
+        .subcommand_required(true)
+        .arg_required_else_help(true)
+        .subcommand(
+            Command::new("run")
+                .about("Run one day against an input file")
+                .arg(
+                    Arg::new("day")
+                        .long("day")
+                        .short('d')
+                        .required(true)
+                        .value_parser(value_parser!(u32).range(1..=25)),
+                )
+                .arg(
+                    Arg::new("part")
+                        .long("part")
+                        .short('p')
+                        .value_parser(value_parser!(u32).range(1..=2))
+                        .help("Only run this part, both parts run by default"),
+                )
+                .arg(
+                    Arg::new("input")
+                        .long("input")
+                        .short('i')
+                        .required(true)
+                        .value_parser(value_parser!(PathBuf))
+                        .help("Puzzle input file, or - to read stdin"),
+                ),
+        )
+        .subcommand(
+            Command::new("run-all")
+                .about("Run every day that has an input file")
+                .args(input_dir_args()),
+        )
+        .subcommand(
+            Command::new("list")
+                .about("List the available days and their input files")
+                .args(input_dir_args()),
+        )
+        .subcommand(
+            Command::new("bench")
+                .about("Time the generator and both parts of every day")
+                .args(input_dir_args())
+                .arg(
+                    Arg::new("day")
+                        .long("day")
+                        .short('d')
+                        .value_parser(value_parser!(u32).range(1..=25))
+                        .help("Only time this day"),
+                )
+                .arg(
+                    Arg::new("runs")
+                        .long("runs")
+                        .short('n')
+                        .value_parser(value_parser!(usize))
+                        .default_value("1")
+                        .help("Run each day this many times and report the median"),
+                )
+                .arg(
+                    Arg::new("sort")
+                        .long("sort")
+                        .value_parser(["day", "parse", "part1", "part2", "total"])
+                        .default_value("day")
+                        .help("Column to sort the table by, slowest first"),
+                )
+                .arg(
+                    Arg::new("json")
+                        .long("json")
+                        .action(ArgAction::SetTrue)
+                        .help("Print JSON instead of a table"),
+                ),
+        )
+        .subcommand(
+            Command::new("bits")
+                .about("Work with day 16 BITS transmissions")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("show")
+                        .about("Show what a transmission computes")
+                        .args(transmission_args())
+                        .arg(
+                            Arg::new("format")
+                                .long("format")
+                                .short('f')
+                                .value_parser(["infix", "sexpr", "tree"])
+                                .default_value("infix"),
+                        ),
+                )
+                .subcommand(
+                    Command::new("asm")
+                        .about("Compile an expression like 'sum(1, 2) * 3' into a transmission")
+                        .arg(
+                            Arg::new("expr")
+                                .help("Expression to compile, read from --input if not given"),
+                        )
+                        .arg(
+                            Arg::new("input")
+                                .long("input")
+                                .short('i')
+                                .value_parser(value_parser!(PathBuf))
+                                .conflicts_with("expr")
+                                .help("File holding the expression, or - to read stdin"),
+                        ),
+                )
+                .subcommand(
+                    Command::new("eval")
+                        .about("Evaluate a transmission")
+                        .args(transmission_args())
+                        .arg(
+                            Arg::new("big")
+                                .long("big")
+                                .action(ArgAction::SetTrue)
+                                .help("Evaluate exactly instead of failing past 128 bits"),
+                        ),
+                ),
+        )
+        .subcommand(
+            Command::new("alu")
+                .about("Analyse day 24 ALU programs")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("constraints")
+                        .about("Run a program symbolically and print what its inputs must satisfy")
+                        .arg(program_arg()),
+                )
+                .subcommand(
+                    Command::new("brute")
+                        .about("Try every value of some digits of a model number")
+                        .arg(program_arg())
+                        .arg(
+                            Arg::new("digits")
+                                .required(true)
+                                .help("Model number supplying the digits that stay fixed"),
+                        )
+                        .arg(
+                            Arg::new("free")
+                                .long("free")
+                                .short('f')
+                                .required(true)
+                                .value_delimiter(',')
+                                .value_parser(value_parser!(usize))
+                                .help("Indices of the digits to vary, e.g. 0,1,2"),
+                        ),
+                )
+                .subcommand(
+                    Command::new("debug")
+                        .about("Step through a program interactively")
+                        .arg(program_arg())
+                        .arg(
+                            Arg::new("digits")
+                                .required(true)
+                                .help("Input digits, most significant first"),
+                        )
+                        .arg(
+                            Arg::new("break")
+                                .long("break")
+                                .short('b')
+                                .action(ArgAction::Append)
+                                .value_parser(value_parser!(usize))
+                                .help("Set a breakpoint on this instruction index"),
+                        ),
+                )
+                .subcommand(
+                    Command::new("disasm")
+                        .about("List the blocks of a MONAD program and how they use z as a stack")
+                        .arg(program_arg()),
+                ),
+        )
+        .subcommand(
+            Command::new("amphipods")
+                .about("Show the cheapest way to sort a day 23 burrow")
+                .arg(
+                    Arg::new("input")
+                        .long("input")
+                        .short('i')
+                        .required(true)
+                        .value_parser(value_parser!(PathBuf))
+                        .help("Day 23 puzzle input, or - to read stdin"),
+                )
+                .arg(
+                    Arg::new("unfold")
+                        .long("unfold")
+                        .action(ArgAction::SetTrue)
+                        .help("Add the two hidden rows from part 2"),
+                )
+                .arg(
+                    Arg::new("rows")
+                        .long("rows")
+                        .requires("unfold")
+                        .value_parser(value_parser!(PathBuf))
+                        .help("Unfold these diagram rows instead of the part 2 ones"),
+                )
+                .arg(
+                    Arg::new("at")
+                        .long("at")
+                        .requires("unfold")
+                        .value_parser(value_parser!(usize))
+                        .default_value("1")
+                        .help("How many rows down from the top to unfold them"),
+                )
+                .arg(
+                    Arg::new("delay")
+                        .long("delay")
+                        .value_parser(value_parser!(u64))
+                        .default_value("500")
+                        .help("Milliseconds to show each step of the animation"),
+                )
+                .arg(
+                    Arg::new("list")
+                        .long("list")
+                        .short('l')
+                        .action(ArgAction::SetTrue)
+                        .help("Only list the moves, don't animate them"),
+                ),
+        )
+        .subcommand(
+            Command::new("route")
+                .about("Show the lowest risk route through a day 15 map")
+                .arg(
+                    Arg::new("input")
+                        .long("input")
+                        .short('i')
+                        .required(true)
+                        .value_parser(value_parser!(PathBuf))
+                        .help("Day 15 puzzle input, or - to read stdin"),
+                )
+                .arg(
+                    Arg::new("tile")
+                        .long("tile")
+                        .short('t')
+                        .value_parser(value_parser!(u64).range(1..))
+                        .default_value("1")
+                        .help("Repeat the map this many times each way, 5 for part 2"),
+                )
+                .arg(
+                    Arg::new("start")
+                        .long("start")
+                        .value_parser(parse_point)
+                        .help("Start point as x,y, the top left corner by default"),
+                )
+                .arg(
+                    Arg::new("goal")
+                        .long("goal")
+                        .value_parser(parse_point)
+                        .help("Goal point as x,y, the bottom right corner by default"),
+                )
+                .arg(
+                    Arg::new("format")
+                        .long("format")
+                        .short('f')
+                        .value_parser(["term", "text", "coords", "svg"])
+                        .default_value("term"),
+                )
+                .arg(
+                    Arg::new("output")
+                        .long("output")
+                        .short('o')
+                        .value_parser(value_parser!(PathBuf))
+                        .help("Write to this file instead of stdout"),
+                ),
+        )
+        .subcommand(
+            Command::new("verify")
+                .about("Check every recorded answer against what the solvers give now")
+                .arg(
+                    Arg::new("answers")
+                        .long("answers")
+                        .short('a')
+                        .value_parser(value_parser!(PathBuf))
+                        .default_value(DEFAULT_ANSWERS)
+                        .help("Answers file, input paths in it are relative to its directory"),
+                )
+                .arg(
+                    Arg::new("day")
+                        .long("day")
+                        .short('d')
+                        .value_parser(value_parser!(u32).range(1..=25))
+                        .help("Only verify this day"),
+                )
+                .arg(
+                    Arg::new("bless")
+                        .long("bless")
+                        .action(ArgAction::SetTrue)
+                        .help("Write changed answers back to the answers file"),
+                ),
+        )
+}
+
+fn parse_point(s: &str) -> Result<(usize, usize), String> {
+    let (x, y) = s.split_once(',').ok_or("expected x,y")?;
+    let x = x
+        .trim()
+        .parse()
+        .map_err(|_| format!("bad x coordinate {:?}", x))?;
+    let y = y
+        .trim()
+        .parse()
+        .map_err(|_| format!("bad y coordinate {:?}", y))?;
+    Ok((x, y))
+}
+
+fn read_input(path: &Path) -> Result<String, Box<dyn Error>> {
+    if path == Path::new("-") {
+        let mut input = String::new();
+        io::stdin().read_to_string(&mut input)?;
+        Ok(input)
+    } else {
+        read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e).into())
+    }
+}
+
+fn input_path(matches: &ArgMatches, day: u32) -> PathBuf {
+    let dir = matches.get_one::<PathBuf>("input-dir").unwrap();
+    let pattern = matches.get_one::<String>("pattern").unwrap();
+    dir.join(pattern.replace("{day}", &day.to_string()))
+}
+
+fn print_answers(day: u32, parts: &[u32], answers: &[Answer]) {
+    for (part, answer) in parts.iter().zip(answers) {
+        if let Answer::Art(_) = answer {
+            println!("Day {} - Part {}:\n{}", day, part, answer);
+        } else {
+            println!("Day {} - Part {}: {}", day, part, answer);
+        }
+    }
+}
+
+fn run(matches: &ArgMatches) -> Result<(), Box<dyn Error>> {
+    let day = *matches.get_one::<u32>("day").unwrap();
+    let parts = match matches.get_one::<u32>("part") {
+        Some(&part) => vec![part],
+        None => vec![1, 2],
+    };
+    let path = matches.get_one::<PathBuf>("input").unwrap();
+    let input = read_input(path)?;
+    let answers = solve(day, &parts, &input).map_err(|e| format!("{}: {}", path.display(), e))?;
+    print_answers(day, &parts, &answers);
+    Ok(())
+}
+
+fn run_all(matches: &ArgMatches) -> Result<(), Box<dyn Error>> {
+    let mut failures = 0;
+    for solution in SOLUTIONS {
+        let day = solution.day();
+        let path = input_path(matches, day);
+        if !path.exists() {
+            println!("Day {}: skipped, no input at {}", day, path.display());
+            continue;
+        }
+        let parts = [1, 2];
+        match read_input(&path).and_then(|input| solve(day, &parts, &input)) {
+            Ok(answers) => print_answers(day, &parts, &answers),
+            Err(e) => {
+                println!("Day {}: {}: {}", day, path.display(), e);
+                failures += 1;
+            }
+        }
+    }
+
+    if failures > 0 {
+        Err(format!("{} day(s) failed", failures).into())
+    } else {
+        Ok(())
+    }
+}
+
+fn list(matches: &ArgMatches) -> Result<(), Box<dyn Error>> {
+    for solution in SOLUTIONS {
+        let day = solution.day();
+        let path = input_path(matches, day);
+        let status = if path.exists() { "" } else { " (missing)" };
+        println!("Day {:>2}  {}{}", day, path.display(), status);
+    }
+    Ok(())
+}
+
+fn bench(matches: &ArgMatches) -> Result<(), Box<dyn Error>> {
+    let day = matches.get_one::<u32>("day").copied();
+    let runs = *matches.get_one::<usize>("runs").unwrap();
+    let key: SortKey = matches.get_one::<String>("sort").unwrap().parse()?;
+
+    let mut timings = vec![];
+    let mut failed = 0;
+    for solution in SOLUTIONS {
+        if day.is_some() && day != Some(solution.day()) {
+            continue;
+        }
+        let path = input_path(matches, solution.day());
+        if !path.exists() {
+            eprintln!(
+                "Day {}: skipped, no input at {}",
+                solution.day(),
+                path.display()
+            );
+            continue;
+        }
+        // Report a day that doesn't run and carry on with the rest
+        match read_input(&path)
+            .and_then(|input| timing::time(*solution, &input, path.clone(), runs))
+        {
+            Ok(timing) => timings.push(timing),
+            Err(e) => {
+                eprintln!("Day {}: failed, {}: {}", solution.day(), path.display(), e);
+                failed += 1;
+            }
+        }
+    }
+
+    timing::sort(&mut timings, key);
+    if matches.get_flag("json") {
+        println!("{}", serde_json::to_string_pretty(&timings)?);
+    } else {
+        println!("{}", timing::table(&timings));
+    }
+    if failed > 0 {
+        Err("some days failed to run".into())
+    } else {
+        Ok(())
+    }
+}
+
+fn transmission_args() -> [Arg; 2] {
+    [
+        Arg::new("hex").help("Hex transmission, read from --input if not given"),
+        Arg::new("input")
+            .long("input")
+            .short('i')
+            .value_parser(value_parser!(PathBuf))
+            .conflicts_with("hex")
+            .help("File holding the transmission, or - to read stdin"),
+    ]
+}
+
+fn read_packet(matches: &ArgMatches) -> Result<bits::Packet, Box<dyn Error>> {
+    let hex = match (
+        matches.get_one::<String>("hex"),
+        matches.get_one::<PathBuf>("input"),
+    ) {
+        (Some(hex), _) => hex.clone(),
+        (None, Some(path)) => read_input(path)?,
+        (None, None) => return Err("give a transmission or --input".into()),
+    };
+    Ok(bits::Packet::parse(hex.trim())?)
+}
+
+fn bits_show(matches: &ArgMatches) -> Result<(), Box<dyn Error>> {
+    let packet = read_packet(matches)?;
+    match matches.get_one::<String>("format").unwrap().as_str() {
+        "infix" => println!("{}", packet),
+        "sexpr" => println!("{}", packet.to_sexpr()),
+        _ => println!("{}", packet.to_tree()),
+    }
+    Ok(())
+}
+
+fn bits(matches: &ArgMatches) -> Result<(), Box<dyn Error>> {
+    match matches.subcommand() {
+        Some(("show", sub)) => bits_show(sub),
+        Some(("asm", sub)) => {
+            let source = match (
+                sub.get_one::<String>("expr"),
+                sub.get_one::<PathBuf>("input"),
+            ) {
+                (Some(expr), _) => expr.clone(),
+                (None, Some(path)) => read_input(path)?,
+                (None, None) => return Err("give an expression or --input".into()),
+            };
+            println!("{}", bits::Packet::assemble(&source)?.to_hex());
+            Ok(())
+        }
+        Some(("eval", sub)) => {
+            let packet = read_packet(sub)?;
+            if sub.get_flag("big") {
+                println!("{}", packet.big_value()?);
+            } else {
+                println!("{}", packet.value()?);
+            }
+            Ok(())
+        }
+        _ => unreachable!("clap requires a subcommand"),
+    }
+}
+
+fn program_arg() -> Arg {
+    Arg::new("input")
+        .long("input")
+        .short('i')
+        .required(true)
+        .value_parser(value_parser!(PathBuf))
+        .help("ALU program, or - to read stdin")
+}
+
+fn read_program(matches: &ArgMatches) -> Result<Vec<day24::Instruction>, Box<dyn Error>> {
+    let path = matches.get_one::<PathBuf>("input").unwrap();
+    day24::load_input(&read_input(path)?).map_err(|e| format!("{}: {}", path.display(), e).into())
+}
+
+fn alu_constraints(matches: &ArgMatches) -> Result<(), Box<dyn Error>> {
+    let program = read_program(matches)?;
+    let paths = day24::digit_constraints(&program).ok_or("too many paths to follow")?;
+    if paths.is_empty() {
+        return Err("no input can leave z at 0".into());
+    }
+    for (i, constraints) in paths.iter().enumerate() {
+        if paths.len() > 1 {
+            println!("path {}:", i + 1);
+        }
+        for constraint in constraints {
+            println!("{}", constraint);
+        }
+        let digits = day24::input_count(&program);
+        if let Some((largest, smallest)) = day24::solve_constraints(constraints, digits) {
+            println!("largest: {}", largest);
+            println!("smallest: {}", smallest);
+        }
+    }
+    Ok(())
+}
+
+fn alu_brute(matches: &ArgMatches) -> Result<(), Box<dyn Error>> {
+    let program = read_program(matches)?;
+    let compiled = day24::compile(&program);
+    let digits: Vec<i64> = matches
+        .get_one::<String>("digits")
+        .unwrap()
+        .chars()
+        .map(|c| {
+            c.to_digit(10)
+                .map(|d| d as i64)
+                .ok_or("digits must be 0 to 9")
+        })
+        .collect::<Result<_, _>>()?;
+    if digits.len() != compiled.inputs() {
+        return Err(format!("the program reads {} digits", compiled.inputs()).into());
+    }
+    let free: Vec<usize> = matches.get_many("free").unwrap().copied().collect();
+    if let Some(idx) = free.iter().find(|&&idx| idx >= digits.len()) {
+        return Err(format!("there's no digit {}", idx).into());
+    }
+
+    let start = Instant::now();
+    let accepted = day24::brute_force(&compiled, &digits, &free);
+    let elapsed = start.elapsed().as_secs_f64();
+    let count = 9f64.powi(free.len() as i32);
+    println!(
+        "{} of {} accepted in {:.2} s, {:.1}M inputs/s",
+        accepted.len(),
+        count,
+        elapsed,
+        count / elapsed / 1e6
+    );
+    if let (Some(smallest), Some(largest)) = (accepted.first(), accepted.last()) {
+        println!("largest: {}", largest);
+        println!("smallest: {}", smallest);
+    }
+    Ok(())
+}
+
+fn alu_debug(matches: &ArgMatches) -> Result<(), Box<dyn Error>> {
+    let program = read_program(matches)?;
+    let digits = matches.get_one::<String>("digits").unwrap();
+    let mut debugger = day24::Debugger::new(&program, digits)?;
+    for &idx in matches.get_many::<usize>("break").into_iter().flatten() {
+        debugger.toggle_breakpoint(idx);
+    }
+
+    let mut screen = io::stdout().into_raw_mode()?.into_alternate_screen()?;
+    write!(screen, "{}", cursor::Hide)?;
+    let mut keys = io::stdin().keys();
+    let mut cursor = 0;
+    let mut status = String::new();
+    loop {
+        let (_, rows) = termion::terminal_size()?;
+        write!(
+            screen,
+            "{}{}{}",
+            clear::All,
+            cursor::Goto(1, 1),
+            debugger.render(cursor, rows as usize, &status)
+        )?;
+        screen.flush()?;
+
+        let result = match keys.next().transpose()? {
+            None | Some(Key::Char('q')) | Some(Key::Ctrl('c')) => break,
+            Some(Key::Char('s')) | Some(Key::Right) => debugger.step(),
+            Some(Key::Char('b')) | Some(Key::Left) => debugger.step_back(),
+            Some(Key::Char('n')) => debugger.next_block(),
+            Some(Key::Char('p')) => debugger.previous_block(),
+            Some(Key::Char('c')) => debugger.resume(),
+            Some(Key::Char('r')) => debugger.reverse(),
+            Some(Key::Char('j')) | Some(Key::Down) => {
+                cursor = (cursor + 1).min(program.len().saturating_sub(1));
+                continue;
+            }
+            Some(Key::Char('k')) | Some(Key::Up) => {
+                cursor = cursor.saturating_sub(1);
+                continue;
+            }
+            Some(Key::Char('t')) => {
+                debugger.toggle_breakpoint(cursor);
+                continue;
+            }
+            Some(_) => continue,
+        };
+        status = match result {
+            Ok(()) => String::new(),
+            Err(stop) => stop.to_string(),
+        };
+        cursor = debugger.pc().min(program.len().saturating_sub(1));
+    }
+    write!(screen, "{}", cursor::Show)?;
+    Ok(())
+}
+
+fn alu(matches: &ArgMatches) -> Result<(), Box<dyn Error>> {
+    match matches.subcommand() {
+        Some(("brute", sub)) => alu_brute(sub),
+        Some(("constraints", sub)) => alu_constraints(sub),
+        Some(("debug", sub)) => alu_debug(sub),
+        Some(("disasm", sub)) => {
+            let blocks = day24::recognize_monad(&read_program(sub)?)?;
+            println!("{}", day24::monad_listing(&blocks));
+            Ok(())
+        }
+        _ => unreachable!("clap requires a subcommand"),
+    }
+}
+
+fn amphipods(matches: &ArgMatches) -> Result<(), Box<dyn Error>> {
+    let path = matches.get_one::<PathBuf>("input").unwrap();
+    let mut burrow =
+        day23::load_input(&read_input(path)?).map_err(|e| format!("{}: {}", path.display(), e))?;
+    if matches.get_flag("unfold") {
+        let rows = match matches.get_one::<PathBuf>("rows") {
+            Some(rows) => read_input(rows)?,
+            None => burrow.indent(day23::PART2_ROWS),
+        };
+        let at = *matches.get_one::<usize>("at").unwrap();
+        burrow = burrow.unfold(at, &rows)?;
+    }
+    let moves = burrow
+        .best_moves()
+        .ok_or("the amphipods can't all get home")?;
+    let total: usize = moves.iter().map(|m| m.cost).sum();
+
+    if matches.get_flag("list") {
+        for m in &moves {
+            println!("{}", m);
+        }
+        println!("Total energy {}", total);
+        return Ok(());
+    }
+
+    let delay = Duration::from_millis(*matches.get_one::<u64>("delay").unwrap());
+    let mut stdout = io::stdout();
+    let mut spent = 0;
+    for (i, frame) in burrow.frames(&moves).iter().enumerate() {
+        let caption = match i.checked_sub(1).map(|i| &moves[i]) {
+            Some(m) => {
+                spent += m.cost;
+                m.to_string()
+            }
+            None => "Start".to_string(),
+        };
+        writeln!(stdout, "{}{}{}\n", clear::All, cursor::Goto(1, 1), frame)?;
+        writeln!(stdout, "Move {}/{}: {}", i, moves.len(), caption)?;
+        writeln!(stdout, "Energy {}/{}", spent, total)?;
+        stdout.flush()?;
+        thread::sleep(delay);
+    }
+    Ok(())
+}
+
+fn route(matches: &ArgMatches) -> Result<(), Box<dyn Error>> {
+    let path = matches.get_one::<PathBuf>("input").unwrap();
+    let tile = *matches.get_one::<u64>("tile").unwrap() as usize;
+    let grid =
+        day15::load_input(&read_input(path)?).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let tiled = day15::TiledMap::new(&grid, tile);
+    let start = matches.get_one("start").copied().unwrap_or((0, 0));
+    let goal = matches
+        .get_one("goal")
+        .copied()
+        .unwrap_or((tiled.width() - 1, tiled.height() - 1));
+    let route = day15::lowest_risk(&grid, start, goal, tile).ok_or_else(|| {
+        format!(
+            "start and goal must be inside the {}x{} map",
+            tiled.width(),
+            tiled.height()
+        )
+    })?;
+
+    let rendered = match matches.get_one::<String>("format").unwrap().as_str() {
+        "term" => day15::render_terminal(&tiled, &route.states),
+        "text" => day15::render_text(&tiled, &route.states),
+        "coords" => day15::render_coords(&tiled, &route.states),
+        _ => day15::render_svg(&tiled, &route.states),
+    };
+    match matches.get_one::<PathBuf>("output") {
+        Some(output) => {
+            std::fs::write(output, rendered)?;
+            println!(
+                "Total risk {}, route written to {}",
+                route.cost,
+                output.display()
+            );
+        }
+        None => {
+            println!("{}", rendered);
+            eprintln!("Total risk {}", route.cost);
+        }
+    }
+    Ok(())
+}
+
+fn verify(matches: &ArgMatches) -> Result<(), Box<dyn Error>> {
+    let path = matches.get_one::<PathBuf>("answers").unwrap();
+    let day = matches.get_one::<u32>("day").copied();
+    let bless = matches.get_flag("bless");
+    let mut answers = Answers::load(path)?;
+    let base = path.parent().unwrap_or_else(|| Path::new(""));
+
+    // Panics are reported as failures, keep the default hook from also
+    // dumping them on stderr.
+    std::panic::set_hook(Box::new(|_| {}));
+
+    let (mut passed, mut changed, mut failed) = (0, 0, 0);
+    for expected in answers.inputs.iter_mut() {
+        if day.is_some() && day != Some(expected.day) {
+            continue;
+        }
+        for check in verify::check(expected, base) {
+            let name = format!(
+                "day {:>2} part {}  {}",
+                check.day,
+                check.part,
+                check.path.display()
+            );
+            match check.status {
+                Status::Pass => {
+                    println!("PASS     {}", name);
+                    passed += 1;
+                }
+                Status::Changed(answer) => {
+                    let (want, got) = (&check.expected, answer.to_string());
+                    if want.contains('\n') || got.contains('\n') {
+                        println!("CHANGED  {}\nexpected:\n{}\ngot:\n{}", name, want, got);
+                    } else {
+                        println!("CHANGED  {}  expected {}, got {}", name, want, got);
+                    }
+                    if bless {
+                        expected.set_part(check.part, &answer);
+                    }
+                    changed += 1;
+                }
+                Status::Fail(e) => {
+                    println!("FAIL     {}  {}", name, e);
+                    failed += 1;
+                }
+            }
+        }
+    }
+    let _ = std::panic::take_hook();
+
+    println!("{} passed, {} changed, {} failed", passed, changed, failed);
+    if bless && changed > 0 {
+        answers.save(path)?;
+        println!("updated {}", path.display());
+    }
+
+    if failed > 0 || (changed > 0 && !bless) {
+        Err("answers don't match".into())
+    } else {
+        Ok(())
+    }
+}
+
+fn main() {
+    let matches = cli().get_matches();
+    let result = match matches.subcommand() {
+        Some(("run", sub)) => run(sub),
+        Some(("run-all", sub)) => run_all(sub),
+        Some(("list", sub)) => list(sub),
+        Some(("bench", sub)) => bench(sub),
+        Some(("alu", sub)) => alu(sub),
+        Some(("amphipods", sub)) => amphipods(sub),
+        Some(("bits", sub)) => bits(sub),
+        Some(("route", sub)) => route(sub),
+        Some(("verify", sub)) => verify(sub),
+        _ => unreachable!("clap requires a subcommand"),
+    };
+
+    if let Err(e) = result {
+        eprintln!("error: {}", e);
+        std::process::exit(1);
+    }
+}
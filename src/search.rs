@@ -0,0 +1,191 @@
+use std::cmp::Reverse;
+use std::collections::hash_map::Entry;
+use std::collections::{BinaryHeap, HashMap, VecDeque};
+use std::hash::Hash;
+use std::ops::Add;
+
+/// Anything usable as an edge cost. `Default` has to be zero.
+pub trait Cost: Copy + Ord + Default + Add<Output = Self> {}
+
+impl<T: Copy + Ord + Default + Add<Output = T>> Cost for T {}
+
+/// The cheapest way found from the start to a goal, both ends included.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Route<S, C> {
+    pub states: Vec<S>,
+    pub cost: C,
+}
+
+struct Node<S, C> {
+    state: S,
+    parent: Option<usize>,
+    cost: C,
+}
+
+fn reconstruct<S: Clone, C>(nodes: &[Node<S, C>], mut idx: usize) -> Vec<S> {
+    let mut states = vec![nodes[idx].state.clone()];
+    while let Some(parent) = nodes[idx].parent {
+        states.push(nodes[parent].state.clone());
+        idx = parent;
+    }
+    states.reverse();
+    states
+}
+
+/// Cheapest route from `start` to any state where `is_goal` holds.
+/// `successors` lists the states one step away along with the cost of the
+/// step.
+pub fn dijkstra<S, C, I>(
+    start: S,
+    successors: impl FnMut(&S) -> I,
+    is_goal: impl FnMut(&S) -> bool,
+) -> Option<Route<S, C>>
+where
+    S: Clone + Hash + Eq,
+    C: Cost,
+    I: IntoIterator<Item = (S, C)>,
+{
+    astar(start, successors, |_| C::default(), is_goal)
+}
+
+/// Same as `dijkstra`, but `heuristic` guides the search toward the goal.
+/// It must never overestimate the remaining cost or the route found might
+/// not be the cheapest.
+pub fn astar<S, C, I>(
+    start: S,
+    mut successors: impl FnMut(&S) -> I,
+    mut heuristic: impl FnMut(&S) -> C,
+    mut is_goal: impl FnMut(&S) -> bool,
+) -> Option<Route<S, C>>
+where
+    S: Clone + Hash + Eq,
+    C: Cost,
+    I: IntoIterator<Item = (S, C)>,
+{
+    let mut index = HashMap::new();
+    let mut nodes = vec![];
+    let mut heap = BinaryHeap::new();
+
+    index.insert(start.clone(), 0);
+    heap.push(Reverse((heuristic(&start), C::default(), 0)));
+    nodes.push(Node {
+        state: start,
+        parent: None,
+        cost: C::default(),
+    });
+
+    while let Some(Reverse((_, cost, idx))) = heap.pop() {
+        // Stale entry, this node was reached more cheaply since it was pushed
+        if cost > nodes[idx].cost {
+            continue;
+        }
+        if is_goal(&nodes[idx].state) {
+            return Some(Route {
+                states: reconstruct(&nodes, idx),
+                cost,
+            });
+        }
+
+        for (next, step) in successors(&nodes[idx].state) {
+            let next_cost = cost + step;
+            match index.entry(next) {
+                Entry::Occupied(e) => {
+                    let node = &mut nodes[*e.get()];
+                    if next_cost < node.cost {
+                        node.cost = next_cost;
+                        node.parent = Some(idx);
+                        let estimate = next_cost + heuristic(&node.state);
+                        heap.push(Reverse((estimate, next_cost, *e.get())));
+                    }
+                }
+                Entry::Vacant(e) => {
+                    let estimate = next_cost + heuristic(e.key());
+                    heap.push(Reverse((estimate, next_cost, nodes.len())));
+                    nodes.push(Node {
+                        state: e.key().clone(),
+                        parent: Some(idx),
+                        cost: next_cost,
+                    });
+                    e.insert(nodes.len() - 1);
+                }
+            }
+        }
+    }
+    None
+}
+
+/// Shortest route by number of steps, every step costing the same.
+pub fn bfs<S, I>(
+    start: S,
+    mut successors: impl FnMut(&S) -> I,
+    mut is_goal: impl FnMut(&S) -> bool,
+) -> Option<Route<S, usize>>
+where
+    S: Clone + Hash + Eq,
+    I: IntoIterator<Item = S>,
+{
+    let mut index = HashMap::new();
+    let mut nodes = vec![];
+    let mut queue = VecDeque::new();
+
+    index.insert(start.clone(), 0);
+    queue.push_back(0);
+    nodes.push(Node {
+        state: start,
+        parent: None,
+        cost: 0,
+    });
+
+    while let Some(idx) = queue.pop_front() {
+        if is_goal(&nodes[idx].state) {
+            return Some(Route {
+                states: reconstruct(&nodes, idx),
+                cost: nodes[idx].cost,
+            });
+        }
+
+        for next in successors(&nodes[idx].state) {
+            if let Entry::Vacant(e) = index.entry(next) {
+                queue.push_back(nodes.len());
+                nodes.push(Node {
+                    state: e.key().clone(),
+                    parent: Some(idx),
+                    cost: nodes[idx].cost + 1,
+                });
+                e.insert(nodes.len() - 1);
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // 0 -> 1 -> 3 is shorter in steps, 0 -> 2 -> 3 is cheaper
+    fn graph(node: &u32) -> Vec<(u32, u32)> {
+        match node {
+            0 => vec![(1, 1), (2, 2)],
+            1 => vec![(3, 10)],
+            2 => vec![(3, 3)],
+            _ => vec![],
+        }
+    }
+
+    #[test]
+    fn test_search() {
+        let route = dijkstra(0, graph, |&n| n == 3).unwrap();
+        assert_eq!(route.states, vec![0, 2, 3]);
+        assert_eq!(route.cost, 5);
+
+        let route = astar(0, graph, |&n| if n == 3 { 0 } else { 1 }, |&n| n == 3).unwrap();
+        assert_eq!(route.cost, 5);
+
+        let route = bfs(0, |n| graph(n).into_iter().map(|(n, _)| n), |&n| n == 3).unwrap();
+        assert_eq!(route.states, vec![0, 1, 3]);
+        assert_eq!(route.cost, 2);
+
+        assert!(dijkstra(1, graph, |&n| n == 2).is_none());
+    }
+}
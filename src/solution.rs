@@ -0,0 +1,187 @@
+use crate::error::ParseError;
+use crate::grid::Grid;
+use crate::{
+    bits, day12, day13, day14, day17, day18, day19, day2, day21, day22, day23, day24, day25, day4,
+    day5, day6,
+};
+use std::any::Any;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+
+/// What a solver hands back, independent of the concrete type each day's
+/// `partN` function returns.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Answer {
+    Int(i128),
+    Text(String),
+    /// Multi-line ASCII art, like the letters folded out of the paper on day 13.
+    Art(String),
+}
+
+impl fmt::Display for Answer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Answer::Int(value) => write!(f, "{}", value),
+            Answer::Text(text) => write!(f, "{}", text),
+            Answer::Art(art) => write!(f, "{}", art),
+        }
+    }
+}
+
+pub type SolveResult = Result<Answer, Box<dyn Error>>;
+
+/// Conversion from whatever a day's `partN` returns into an `Answer`.
+pub trait IntoAnswer {
+    fn into_answer(self) -> SolveResult;
+}
+
+macro_rules! int_answer {
+    ($($t:ty),*) => {
+        $(impl IntoAnswer for $t {
+            fn into_answer(self) -> SolveResult {
+                Ok(Answer::Int(self as i128))
+            }
+        })*
+    };
+}
+
+int_answer!(i32, i64, u32, u64, usize);
+
+impl IntoAnswer for u128 {
+    fn into_answer(self) -> SolveResult {
+        match i128::try_from(self) {
+            Ok(value) => Ok(Answer::Int(value)),
+            Err(_) => Ok(Answer::Text(self.to_string())),
+        }
+    }
+}
+
+impl IntoAnswer for String {
+    fn into_answer(self) -> SolveResult {
+        if self.contains('\n') {
+            Ok(Answer::Art(self))
+        } else {
+            Ok(Answer::Text(self))
+        }
+    }
+}
+
+impl<T: IntoAnswer, E: Error + 'static> IntoAnswer for Result<T, E> {
+    fn into_answer(self) -> SolveResult {
+        match self {
+            Ok(value) => value.into_answer(),
+            Err(e) => Err(Box::new(e)),
+        }
+    }
+}
+
+/// A day's parsed puzzle input. Only the solution that produced it can look
+/// inside.
+pub struct Parsed(Box<dyn Any + Send + Sync>);
+
+impl Parsed {
+    fn downcast<T: 'static>(&self, day: u32) -> Result<&T, Box<dyn Error>> {
+        self.0
+            .downcast_ref::<T>()
+            .ok_or_else(|| format!("input wasn't parsed by the day {} generator", day).into())
+    }
+}
+
+/// One day's puzzle, wired up to its generator and both solvers.
+pub trait Solution: Sync {
+    fn day(&self) -> u32;
+    fn parse(&self, input: &str) -> Result<Parsed, ParseError>;
+    fn part1(&self, input: &Parsed) -> SolveResult;
+    fn part2(&self, input: &Parsed) -> SolveResult;
+
+    fn part(&self, part: u32, input: &Parsed) -> SolveResult {
+        match part {
+            1 => self.part1(input),
+            2 => self.part2(input),
+            _ => Err(format!("there is no part {}", part).into()),
+        }
+    }
+}
+
+macro_rules! solutions {
+    ($($day:literal => $module:ident as $name:ident: $input:ty),* $(,)?) => {
+        $(
+            pub struct $name;
+
+            impl Solution for $name {
+                fn day(&self) -> u32 {
+                    $day
+                }
+
+                fn parse(&self, input: &str) -> Result<Parsed, ParseError> {
+                    Ok(Parsed(Box::new(crate::$module::load_input(input)?)))
+                }
+
+                fn part1(&self, input: &Parsed) -> SolveResult {
+                    let input = input.downcast::<$input>($day)?;
+                    crate::$module::part1(input).into_answer()
+                }
+
+                fn part2(&self, input: &Parsed) -> SolveResult {
+                    let input = input.downcast::<$input>($day)?;
+                    crate::$module::part2(input).into_answer()
+                }
+            }
+        )*
+
+        /// Every day, in order.
+        pub static SOLUTIONS: &[&dyn Solution] = &[$(&$name),*];
+    };
+}
+
+solutions! {
+    1 => day1 as Day1: Vec<i64>,
+    2 => day2 as Day2: Vec<day2::Command>,
+    3 => day3 as Day3: Vec<String>,
+    4 => day4 as Day4: (Vec<u8>, Vec<day4::Board>),
+    5 => day5 as Day5: Vec<day5::Segment>,
+    6 => day6 as Day6: Vec<day6::Fish>,
+    7 => day7 as Day7: Vec<i64>,
+    8 => day8 as Day8: Vec<(Vec<String>, Vec<String>)>,
+    9 => day9 as Day9: Grid<u32>,
+    10 => day10 as Day10: Vec<String>,
+    11 => day11 as Day11: Grid<u32>,
+    12 => day12 as Day12: day12::CaveSystem,
+    13 => day13 as Day13: (HashMap<(usize, usize), u8>, Vec<day13::Fold>),
+    14 => day14 as Day14: day14::InputData,
+    15 => day15 as Day15: Grid<u32>,
+    16 => day16 as Day16: bits::Transmission,
+    17 => day17 as Day17: day17::Rectangle,
+    18 => day18 as Day18: Vec<Vec<day18::FishChar>>,
+    19 => day19 as Day19: Vec<day19::Scanner>,
+    20 => day20 as Day20: (Vec<u8>, Grid<u8>),
+    21 => day21 as Day21: Vec<day21::Player>,
+    22 => day22 as Day22: Vec<day22::Instruction>,
+    23 => day23 as Day23: day23::Burrow,
+    24 => day24 as Day24: Vec<day24::Instruction>,
+    25 => day25 as Day25: Grid<Option<day25::Cucumber>>,
+}
+
+pub fn get(day: u32) -> Option<&'static dyn Solution> {
+    SOLUTIONS.iter().copied().find(|s| s.day() == day)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::fs::read_to_string;
+
+    #[test]
+    fn test_registry() {
+        let days: Vec<_> = SOLUTIONS.iter().map(|s| s.day()).collect();
+        assert_eq!(days, (1..=25).collect::<Vec<_>>());
+
+        let solution = get(1).unwrap();
+        let input = read_to_string("input/2021/01.txt").unwrap();
+        let input = solution.parse(&input).unwrap();
+        assert_eq!(solution.part1(&input).unwrap(), Answer::Int(7));
+        assert_eq!(solution.part(2, &input).unwrap(), Answer::Int(5));
+        assert!(get(2).unwrap().part1(&input).is_err());
+    }
+}
@@ -1,13 +1,14 @@
+use crate::error::{parse_token, ParseError};
 use std::collections::{HashMap, HashSet};
 use regex::Regex;
 
 #[aoc_generator(dayNN)]
-pub fn load_input(input: &str) -> Vec<u64> {
+pub fn load_input(input: &str) -> Result<Vec<u64>, ParseError> {
     let mut output = vec![];
-    for line in input.lines() {
-        output.push(line.parse().unwrap());
+    for (idx, line) in input.lines().enumerate() {
+        output.push(parse_token(idx, line, Some(line), "a number")?);
     }
-    output
+    Ok(output)
 }
 
 #[aoc(dayNN, part1)]
@@ -30,14 +31,14 @@ mod test {
     #[test]
     fn test_part1() {
         let input = read_to_string("input/XXa.txt").unwrap();
-        let input = load_input(&input);
+        let input = load_input(&input).unwrap();
         assert_eq!(part1(&input), 0);
     }
 
     #[test]
     fn test_part2() {
         let input = read_to_string("input/XXa.txt").unwrap();
-        let input = load_input(&input);
+        let input = load_input(&input).unwrap();
         assert_eq!(part2(&input), 0);
     }
 }
@@ -0,0 +1,190 @@
+use crate::solution::Solution;
+use serde::{Serialize, Serializer};
+use std::error::Error;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+/// Median time spent in each phase of one day, over `runs` runs.
+#[derive(Clone, Debug, Serialize)]
+pub struct Timing {
+    pub day: u32,
+    pub input: PathBuf,
+    pub runs: usize,
+    #[serde(rename = "parse_ms", serialize_with = "millis")]
+    pub parse: Duration,
+    #[serde(rename = "part1_ms", serialize_with = "millis")]
+    pub part1: Duration,
+    #[serde(rename = "part2_ms", serialize_with = "millis")]
+    pub part2: Duration,
+}
+
+fn millis<S: Serializer>(d: &Duration, s: S) -> Result<S::Ok, S::Error> {
+    s.serialize_f64(d.as_secs_f64() * 1000.0)
+}
+
+impl Timing {
+    pub fn total(&self) -> Duration {
+        self.parse + self.part1 + self.part2
+    }
+}
+
+fn median(mut times: Vec<Duration>) -> Duration {
+    times.sort();
+    times[times.len() / 2]
+}
+
+fn timed<T>(times: &mut Vec<Duration>, f: impl FnOnce() -> T) -> T {
+    let start = Instant::now();
+    let result = f();
+    times.push(start.elapsed());
+    result
+}
+
+/// Run the generator and both parts `runs` times, timing each phase on its own.
+pub fn time(
+    solution: &dyn Solution,
+    input: &str,
+    path: PathBuf,
+    runs: usize,
+) -> Result<Timing, Box<dyn Error>> {
+    let runs = runs.max(1);
+    let (mut parse, mut part1, mut part2) = (vec![], vec![], vec![]);
+    for _ in 0..runs {
+        let parsed = timed(&mut parse, || solution.parse(input))?;
+        timed(&mut part1, || solution.part1(&parsed))?;
+        timed(&mut part2, || solution.part2(&parsed))?;
+    }
+
+    Ok(Timing {
+        day: solution.day(),
+        input: path,
+        runs,
+        parse: median(parse),
+        part1: median(part1),
+        part2: median(part2),
+    })
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SortKey {
+    Day,
+    Parse,
+    Part1,
+    Part2,
+    Total,
+}
+
+impl FromStr for SortKey {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<SortKey, String> {
+        match s {
+            "day" => Ok(SortKey::Day),
+            "parse" => Ok(SortKey::Parse),
+            "part1" => Ok(SortKey::Part1),
+            "part2" => Ok(SortKey::Part2),
+            "total" => Ok(SortKey::Total),
+            _ => Err(format!("can't sort by {}", s)),
+        }
+    }
+}
+
+/// Sorts by day ascending, everything else slowest first.
+pub fn sort(timings: &mut [Timing], key: SortKey) {
+    match key {
+        SortKey::Day => timings.sort_by_key(|t| t.day),
+        SortKey::Parse => timings.sort_by_key(|t| std::cmp::Reverse(t.parse)),
+        SortKey::Part1 => timings.sort_by_key(|t| std::cmp::Reverse(t.part1)),
+        SortKey::Part2 => timings.sort_by_key(|t| std::cmp::Reverse(t.part2)),
+        SortKey::Total => timings.sort_by_key(|t| std::cmp::Reverse(t.total())),
+    }
+}
+
+fn format_duration(d: Duration) -> String {
+    let us = d.as_secs_f64() * 1e6;
+    if us < 1000.0 {
+        format!("{:.1} us", us)
+    } else if us < 1e6 {
+        format!("{:.2} ms", us / 1000.0)
+    } else {
+        format!("{:.2} s", us / 1e6)
+    }
+}
+
+pub fn table(timings: &[Timing]) -> String {
+    let mut out = format!(
+        "{:>3}  {:>10}  {:>10}  {:>10}  {:>10}\n",
+        "day", "parse", "part1", "part2", "total"
+    );
+    for t in timings {
+        out += &format!(
+            "{:>3}  {:>10}  {:>10}  {:>10}  {:>10}\n",
+            t.day,
+            format_duration(t.parse),
+            format_duration(t.part1),
+            format_duration(t.part2),
+            format_duration(t.total())
+        );
+    }
+    let total: Duration = timings.iter().map(|t| t.total()).sum();
+    out += &format!("{:>51}", format_duration(total));
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::solution;
+    use std::fs::read_to_string;
+
+    #[test]
+    fn test_time() {
+        let input = read_to_string("input/2021/01.txt").unwrap();
+        let solution = solution::get(1).unwrap();
+        let mut timings = vec![time(solution, &input, PathBuf::from("01.txt"), 3).unwrap()];
+        assert_eq!(timings[0].runs, 3);
+
+        timings.push(time(solution::get(6).unwrap(), "3,4,3,1,2", PathBuf::new(), 1).unwrap());
+        sort(&mut timings, SortKey::Day);
+        assert_eq!(timings[0].day, 1);
+        assert!(table(&timings).lines().count() == 4);
+        assert!(time(solution, "x", PathBuf::new(), 1).is_err());
+    }
+
+    #[test]
+    fn test_sort() {
+        let ms = Duration::from_millis;
+        let timing = |day, parse, part1, part2| Timing {
+            day,
+            input: PathBuf::from(format!("day{}.txt", day)),
+            runs: 1,
+            parse: ms(parse),
+            part1: ms(part1),
+            part2: ms(part2),
+        };
+        let mut timings = vec![timing(1, 5, 1, 1), timing(2, 1, 2, 9), timing(3, 1, 8, 1)];
+        let days = |timings: &[Timing]| timings.iter().map(|t| t.day).collect::<Vec<_>>();
+        sort(&mut timings, SortKey::Parse);
+        assert_eq!(days(&timings), vec![1, 2, 3]);
+        sort(&mut timings, SortKey::Part1);
+        assert_eq!(days(&timings), vec![3, 2, 1]);
+        sort(&mut timings, SortKey::Total);
+        assert_eq!(days(&timings), vec![2, 3, 1]);
+        sort(&mut timings, SortKey::Day);
+        assert_eq!(days(&timings), vec![1, 2, 3]);
+
+        let json = serde_json::to_value(&timings[1]).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "day": 2,
+                "input": "day2.txt",
+                "runs": 1,
+                "parse_ms": 1.0,
+                "part1_ms": 2.0,
+                "part2_ms": 9.0,
+            })
+        );
+    }
+}
@@ -0,0 +1,171 @@
+use crate::solution::{self, Answer};
+use serde::{Deserialize, Serialize};
+use std::any::Any;
+use std::error::Error;
+use std::fs;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+
+/// The checked in answers file, one `[[input]]` table per input file.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct Answers {
+    #[serde(default, rename = "input")]
+    pub inputs: Vec<Expected>,
+}
+
+/// Known answers for one day run against one input. Parts without a known
+/// answer are left out and never checked.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Expected {
+    pub day: u32,
+    pub path: PathBuf,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub part1: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub part2: Option<String>,
+}
+
+impl Expected {
+    pub fn part(&self, part: u32) -> Option<&str> {
+        match part {
+            1 => self.part1.as_deref(),
+            2 => self.part2.as_deref(),
+            _ => None,
+        }
+    }
+
+    pub fn set_part(&mut self, part: u32, answer: &Answer) {
+        let answer = Some(answer.to_string());
+        match part {
+            1 => self.part1 = answer,
+            2 => self.part2 = answer,
+            _ => (),
+        }
+    }
+}
+
+impl Answers {
+    pub fn load(path: &Path) -> Result<Answers, Box<dyn Error>> {
+        let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        toml::from_str(&text).map_err(|e| format!("{}: {}", path.display(), e).into())
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        fs::write(path, toml::to_string(self)?)?;
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Status {
+    Pass,
+    /// The solver ran fine but came up with a different answer.
+    Changed(Answer),
+    /// The input couldn't be read or parsed, or the solver errored or panicked.
+    Fail(String),
+}
+
+#[derive(Clone, Debug)]
+pub struct Check {
+    pub day: u32,
+    pub part: u32,
+    pub path: PathBuf,
+    pub expected: String,
+    pub status: Status,
+}
+
+/// Art answers are easy to lose trailing spaces on when editing the answers
+/// file, so those don't count.
+fn normalize(answer: &str) -> String {
+    answer
+        .trim_end()
+        .lines()
+        .map(|l| l.trim_end())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    if let Some(s) = payload.downcast_ref::<&str>() {
+        format!("panicked: {}", s)
+    } else if let Some(s) = payload.downcast_ref::<String>() {
+        format!("panicked: {}", s)
+    } else {
+        String::from("panicked")
+    }
+}
+
+fn catch<T>(f: impl FnOnce() -> Result<T, Box<dyn Error>>) -> Result<T, String> {
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(value)) => Ok(value),
+        Ok(Err(e)) => Err(e.to_string()),
+        Err(payload) => Err(panic_message(payload)),
+    }
+}
+
+/// Run every part of `expected` that has a known answer. The input path is
+/// taken relative to `base`, the directory holding the answers file.
+pub fn check(expected: &Expected, base: &Path) -> Vec<Check> {
+    let parts: Vec<u32> = (1..=2).filter(|&p| expected.part(p).is_some()).collect();
+    let parsed = catch(|| {
+        let solution =
+            solution::get(expected.day).ok_or(format!("no solution for day {}", expected.day))?;
+        let path = base.join(&expected.path);
+        let input = fs::read_to_string(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        Ok((solution, solution.parse(&input)?))
+    });
+
+    parts
+        .into_iter()
+        .map(|part| {
+            let want = expected.part(part).unwrap();
+            let status = match &parsed {
+                Ok((solution, input)) => match catch(|| solution.part(part, input)) {
+                    Ok(answer) if normalize(&answer.to_string()) == normalize(want) => Status::Pass,
+                    Ok(answer) => Status::Changed(answer),
+                    Err(e) => Status::Fail(e),
+                },
+                Err(e) => Status::Fail(e.clone()),
+            };
+            Check {
+                day: expected.day,
+                part,
+                path: expected.path.clone(),
+                expected: String::from(want),
+                status,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_check() {
+        let answers: Answers = toml::from_str(
+            r#"
+            [[input]]
+            day = 1
+            path = "input/2021/01.txt"
+            part1 = "7"
+            part2 = "6"
+
+            [[input]]
+            day = 2
+            path = "input/2021/01.txt"
+            part1 = "150"
+            "#,
+        )
+        .unwrap();
+
+        let checks = check(&answers.inputs[0], Path::new(""));
+        assert_eq!(checks[0].status, Status::Pass);
+        assert_eq!(checks[1].status, Status::Changed(Answer::Int(5)));
+
+        let checks = check(&answers.inputs[1], Path::new(""));
+        assert_eq!(checks.len(), 1);
+        assert!(matches!(checks[0].status, Status::Fail(_)));
+    }
+}